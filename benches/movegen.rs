@@ -0,0 +1,60 @@
+//! Compares move generation of the array `Board` with the `BitBoard` the engines use,
+//! by counting the leaves of the game tree from the opening. Run with `cargo bench`.
+
+use checkers::rules::{BitBoard, Board};
+use std::time::{Duration, Instant};
+
+const DEPTH: u32 = 8;
+const ROUNDS: u32 = 3;
+
+fn perft_array(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for mv in board.legal_moves() {
+        board.apply(&mv);
+        nodes += perft_array(board, depth - 1);
+        board.unapply(&mv);
+    }
+    nodes
+}
+
+fn perft_bits(board: &mut BitBoard, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for mv in board.legal_moves() {
+        board.apply(&mv);
+        nodes += perft_bits(board, depth - 1);
+        board.unapply(&mv);
+    }
+    nodes
+}
+
+// the fastest of a few rounds, to keep noise from other processes out
+fn measure<F: FnMut() -> u64>(name: &str, mut perft: F) -> Duration {
+    let mut best = Duration::MAX;
+    let mut nodes = 0;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        nodes = perft();
+        best = best.min(start.elapsed());
+    }
+    let per_second = nodes as f64 / best.as_secs_f64();
+    println!(
+        "{:<10} depth {} {:>9} nodes {:>10.1?} {:>12.0} nodes/s",
+        name, DEPTH, nodes, best, per_second
+    );
+    best
+}
+
+fn main() {
+    let array = measure("array", || perft_array(&mut Board::new(), DEPTH));
+    let bits = measure("bitboard", || perft_bits(&mut BitBoard::new(), DEPTH));
+    println!(
+        "bitboard is {:.2} times as fast",
+        array.as_secs_f64() / bits.as_secs_f64()
+    );
+}
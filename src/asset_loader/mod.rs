@@ -5,4 +5,4 @@ mod texture_manager;
 mod texture_with_info;
 
 pub use assets::Assets;
-pub use texture_manager::TextureManager;
+pub use texture_manager::{TextureManager, GLYPH_COUNT};
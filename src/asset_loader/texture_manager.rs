@@ -1,9 +1,18 @@
 use crate::asset_loader::texture_with_info::TextureWithInfo;
-use sdl2::render::TextureCreator;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, TextureCreator};
 use sdl2::surface::Surface;
-use sdl2::video::WindowContext;
+use sdl2::ttf::Font;
+use sdl2::video::{Window, WindowContext};
 use std::collections::HashMap;
 
+// the printable ascii characters
+const FIRST_GLYPH: u8 = b' ';
+const LAST_GLYPH: u8 = b'~';
+/// Number of keys taken by `insert_glyphs`.
+pub const GLYPH_COUNT: usize = (LAST_GLYPH - FIRST_GLYPH) as usize + 1;
+
 pub struct TextureManager<'a> {
     texture_creator: &'a TextureCreator<WindowContext>,
     cache: HashMap<usize, TextureWithInfo<'a>>,
@@ -19,11 +28,68 @@ impl<'a> TextureManager<'a> {
 
     pub fn insert_surface_as_texture(&mut self, i: usize, surf: Surface) -> Result<(), String> {
         self.cache
-            .insert(i, TextureWithInfo::new_from(&self.texture_creator, &surf)?);
+            .insert(i, TextureWithInfo::new_from(self.texture_creator, &surf)?);
         Ok(())
     }
 
     pub fn get_texture(&self, i: usize) -> Option<&TextureWithInfo<'a>> {
         self.cache.get(&i)
     }
+
+    /// Renders every printable character under the keys from `first_key` on, so that text only
+    /// known while playing can still be drawn with `draw_text`.
+    pub fn insert_glyphs(
+        &mut self,
+        first_key: usize,
+        font: &Font,
+        color: Color,
+    ) -> Result<(), String> {
+        for (i, glyph) in (FIRST_GLYPH..=LAST_GLYPH).enumerate() {
+            self.insert_surface_as_texture(
+                first_key + i,
+                font.render_char(glyph as char)
+                    .blended(color)
+                    .map_err(|e| e.to_string())?,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn glyph(&self, first_key: usize, c: char) -> Option<&TextureWithInfo<'a>> {
+        let glyph = match c {
+            ' '..='~' => c as u8,
+            _ => b'?',
+        };
+        self.get_texture(first_key + (glyph - FIRST_GLYPH) as usize)
+    }
+
+    /// Width of `text` drawn with the glyphs from `first_key`.
+    pub fn text_width(&self, first_key: usize, text: &str) -> u32 {
+        text.chars()
+            .filter_map(|c| self.glyph(first_key, c))
+            .map(|glyph| glyph.get_texture_info_ref().width)
+            .sum()
+    }
+
+    /// Draws `text` with its top left corner at `x` and `y`, characters without a glyph as '?'.
+    pub fn draw_text(
+        &self,
+        canvas: &mut Canvas<Window>,
+        first_key: usize,
+        text: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        let mut x = x;
+        for glyph in text.chars().filter_map(|c| self.glyph(first_key, c)) {
+            let info = glyph.get_texture_info_ref();
+            canvas.copy(
+                glyph.get_texture_ref(),
+                None,
+                Some(Rect::new(x, y, info.width, info.height)),
+            )?;
+            x += info.width as i32;
+        }
+        Ok(())
+    }
 }
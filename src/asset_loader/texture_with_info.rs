@@ -22,7 +22,7 @@ impl<'a> TextureWithInfo<'a> {
         })
     }
 
-    pub fn get_texture_ref(&self) -> &Texture {
+    pub fn get_texture_ref(&self) -> &Texture<'a> {
         &self.texture
     }
 
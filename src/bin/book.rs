@@ -0,0 +1,64 @@
+//! Builds an opening book from the first moves of PDN game collections.
+
+use checkers::engine::Book;
+use checkers::notation::PdnGame;
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "Usage: book [--plies <count>] [--min-weight <count>] <output file> <games.pdn>...";
+const DEFAULT_PLIES: usize = 16;
+
+fn main() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let mut plies = DEFAULT_PLIES;
+    let mut min_weight = 1;
+    let mut files = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--plies" => {
+                plies = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("Invalid number of plies\n{}", USAGE))?
+            }
+            "--min-weight" => {
+                min_weight = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("Invalid weight\n{}", USAGE))?
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.len() < 2 {
+        return Err(String::from(USAGE));
+    }
+    let output = files.remove(0);
+
+    let mut book = Book::new();
+    let mut games = 0;
+    for path in &files {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        for (number, pdn) in PdnGame::parse_all(&text)?.iter().enumerate() {
+            // one broken game should not spoil a whole collection
+            match pdn.to_game() {
+                Ok(game) => {
+                    book.add_game(&game, plies);
+                    games += 1;
+                }
+                Err(e) => eprintln!("{} game {}: {}", path.display(), number + 1, e),
+            }
+        }
+    }
+    book.prune(min_weight);
+
+    std::fs::write(&output, book.to_string()).map_err(|e| e.to_string())?;
+    println!(
+        "{} positions from {} games written to {}",
+        book.len(),
+        games,
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Counts the leaf nodes of the game tree to a given depth, to check move generation against
+//! published numbers. With `--divide` the count is split up by the first move.
+
+use checkers::notation::{parse_fen, write_move};
+use checkers::rules::{BitBoard, Board};
+use std::time::Instant;
+
+const USAGE: &str = "Usage: perft [--fen <position>] [--divide] [--array] <depth>";
+
+struct Options {
+    fen: Option<String>,
+    divide: bool,
+    array: bool,
+    depth: u32,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut fen = None;
+    let mut divide = false;
+    let mut array = false;
+    let mut depth = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--fen" => {
+                fen = Some(
+                    args.next()
+                        .ok_or_else(|| format!("Missing value for '--fen'\n{}", USAGE))?,
+                )
+            }
+            "--divide" => divide = true,
+            "--array" => array = true,
+            _ => {
+                depth = Some(
+                    arg.parse()
+                        .map_err(|_| format!("Unknown argument '{}'\n{}", arg, USAGE))?,
+                )
+            }
+        }
+    }
+
+    Ok(Options {
+        fen,
+        divide,
+        array,
+        depth: depth.ok_or_else(|| String::from(USAGE))?,
+    })
+}
+
+fn perft(board: &mut BitBoard, depth: u32) -> u64 {
+    let moves = board.legal_moves();
+    if depth <= 1 {
+        return if depth == 0 { 1 } else { moves.len() as u64 };
+    }
+
+    let mut nodes = 0;
+    for mv in &moves {
+        board.apply(mv);
+        nodes += perft(board, depth - 1);
+        board.unapply(mv);
+    }
+    nodes
+}
+
+// the slower array board, to check the two move generators against each other
+fn perft_array(board: &mut Board, depth: u32) -> u64 {
+    let moves = board.legal_moves();
+    if depth <= 1 {
+        return if depth == 0 { 1 } else { moves.len() as u64 };
+    }
+
+    let mut nodes = 0;
+    for mv in &moves {
+        board.apply(mv);
+        nodes += perft_array(board, depth - 1);
+        board.unapply(mv);
+    }
+    nodes
+}
+
+fn main() -> Result<(), String> {
+    let options = parse_options()?;
+    let mut board = match &options.fen {
+        Some(fen) => parse_fen(fen)?,
+        None => Board::new(),
+    };
+    let mut bit_board = BitBoard::from(&board);
+    let count = |board: &mut Board, bit_board: &mut BitBoard, depth| {
+        if options.array {
+            perft_array(board, depth)
+        } else {
+            perft(bit_board, depth)
+        }
+    };
+
+    let start = Instant::now();
+    let mut nodes = 0;
+    if options.divide && options.depth > 0 {
+        let mut moves = board.legal_moves();
+        moves.sort_by_key(|mv| (mv.from.square(), mv.to().square()));
+        for mv in &moves {
+            board.apply(mv);
+            bit_board.apply(mv);
+            let move_nodes = count(&mut board, &mut bit_board, options.depth - 1);
+            board.unapply(mv);
+            bit_board.unapply(mv);
+
+            println!("{:<12} {}", write_move(mv), move_nodes);
+            nodes += move_nodes;
+        }
+        println!();
+    } else {
+        nodes = count(&mut board, &mut bit_board, options.depth);
+    }
+
+    let elapsed = start.elapsed();
+    println!("depth {}: {} nodes", options.depth, nodes);
+    println!(
+        "{:.3}s, {:.0} nodes/s",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the published counts from the starting position
+    const START: [u64; 8] = [7, 49, 302, 1469, 7361, 36768, 179740, 845931];
+
+    #[test]
+    fn counts_from_the_start() {
+        let mut board = Board::new();
+        let mut bit_board = BitBoard::from(&board);
+        for (depth, nodes) in (1..).zip(START) {
+            assert_eq!(perft(&mut bit_board, depth), nodes, "depth {}", depth);
+            assert_eq!(perft_array(&mut board, depth), nodes, "depth {}", depth);
+        }
+    }
+
+    #[test]
+    fn counts_from_a_position_with_kings() {
+        let mut board = parse_fen("B:W18,24,27,28,K10,K15:B12,16,20,K22,K25,K29").unwrap();
+        let mut bit_board = BitBoard::from(&board);
+        assert_eq!(perft(&mut bit_board, 4), 1378);
+        assert_eq!(perft_array(&mut board, 4), 1378);
+    }
+}
@@ -0,0 +1,49 @@
+//! Builds the endgame tablebase the engine can play perfect endgames from.
+
+use checkers::rules::{CaptureRule, Rules};
+use checkers::tablebase::{generate, Material};
+use std::path::PathBuf;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: tablebase [--pieces <count>] [--majority] <output file>";
+const DEFAULT_PIECES: usize = 4;
+
+fn main() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let mut pieces = DEFAULT_PIECES;
+    let mut rules = Rules::default();
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--pieces" => {
+                pieces = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("Invalid piece count\n{}", USAGE))?
+            }
+            "--majority" => rules.capture = CaptureRule::Majority,
+            _ => output = Some(PathBuf::from(arg)),
+        }
+    }
+    let output = output.ok_or_else(|| String::from(USAGE))?;
+
+    let start = Instant::now();
+    let tablebase = generate(pieces, rules, |material: Material, values: &[u8]| {
+        let draws = values.iter().filter(|value| **value == 0).count();
+        println!(
+            "{}{} v {}{}: {:>10} indices, {:>10} decided, {:.1}s",
+            "m".repeat(material.green_men as usize),
+            "K".repeat(material.green_kings as usize),
+            "m".repeat(material.red_men as usize),
+            "K".repeat(material.red_kings as usize),
+            values.len(),
+            values.len() - draws,
+            start.elapsed().as_secs_f64()
+        );
+    })?;
+
+    tablebase.save(&output)?;
+    println!("Saved {}", output.display());
+    Ok(())
+}
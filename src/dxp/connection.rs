@@ -0,0 +1,57 @@
+use crate::dxp::Message;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// Sends and receives DXP messages over TCP, each one closed by a zero byte.
+pub struct Connection {
+    stream: TcpStream,
+    received: Vec<u8>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Result<Connection, String> {
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+        Ok(Connection {
+            stream,
+            received: vec![],
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        let mut bytes = message.to_string().into_bytes();
+        bytes.push(0);
+        self.stream.write_all(&bytes).map_err(|e| e.to_string())
+    }
+
+    /// The next message, `None` when none arrived within `timeout`.
+    pub fn receive(&mut self, timeout: Duration) -> Result<Option<Message>, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(end) = self.received.iter().position(|byte| *byte == 0) {
+                let bytes: Vec<u8> = self.received.drain(..=end).collect();
+                let text = String::from_utf8_lossy(&bytes[..end]);
+                return Message::parse(&text).map(Some);
+            }
+
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left == Duration::from_secs(0) {
+                return Ok(None);
+            }
+            self.stream
+                .set_read_timeout(Some(left))
+                .map_err(|e| e.to_string())?;
+
+            let mut buffer = [0; 512];
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(String::from("The connection was closed")),
+                Ok(length) => self.received.extend_from_slice(&buffer[..length]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                    return Ok(None)
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+}
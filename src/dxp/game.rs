@@ -0,0 +1,411 @@
+use crate::dxp::{Connection, EndReason, Message, VERSION};
+use crate::engine::Worker;
+use crate::rules::{Board, DrawReason, Game, Move, Outcome, Position, Side};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+// how often the connection is looked at while the engine thinks
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// how long the other side may take to answer a game request or the end of a game
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(30);
+// moves the remaining time is spread over once the time control's moves have been played
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// What the initiator proposes to play.
+#[derive(Debug, Clone)]
+pub struct GameSettings {
+    /// The side of the initiator.
+    pub side: Side,
+    /// Thinking time for each side, for the first `moves` moves.
+    pub minutes: u32,
+    pub moves: u32,
+    /// `None` for the usual starting position.
+    pub start: Option<Board>,
+}
+
+impl Default for GameSettings {
+    fn default() -> GameSettings {
+        GameSettings {
+            side: Side::Green,
+            minutes: 5,
+            moves: 75,
+            start: None,
+        }
+    }
+}
+
+// what a message from the other side did to the game
+enum Change {
+    Nothing,
+    Position,
+    Ended,
+}
+
+/// A game against a remote DXP peer, our moves coming from a worker.
+pub struct DxpGame {
+    connection: Connection,
+    side: Side,
+    peer_name: String,
+    game: Game,
+    time_left: Duration,
+    moves: u32,
+    sent_end: bool,
+    is_finished: bool,
+    peer_result: Option<Outcome>,
+    chat: Vec<String>,
+}
+
+impl DxpGame {
+    fn new(connection: Connection, side: Side, peer_name: String, request: &Message) -> DxpGame {
+        let (minutes, moves, start) = match request {
+            Message::GameRequest {
+                minutes,
+                moves,
+                start,
+                ..
+            } => (*minutes, *moves, *start),
+            _ => (0, 0, None),
+        };
+        DxpGame {
+            connection,
+            side,
+            peer_name,
+            game: Game::new(start.unwrap_or_else(Board::new)),
+            time_left: Duration::from_secs(minutes as u64 * 60),
+            moves,
+            sent_end: false,
+            is_finished: false,
+            peer_result: None,
+            chat: vec![],
+        }
+    }
+
+    /// Asks the peer at the other end of `stream` for a game.
+    pub fn initiate(
+        stream: TcpStream,
+        name: &str,
+        settings: &GameSettings,
+    ) -> Result<DxpGame, String> {
+        let mut connection = Connection::new(stream)?;
+        let request = Message::GameRequest {
+            version: VERSION,
+            name: String::from(name),
+            follower: settings.side.opponent(),
+            minutes: settings.minutes,
+            moves: settings.moves,
+            start: settings.start,
+        };
+        connection.send(&request)?;
+
+        match connection.receive(ANSWER_TIMEOUT)? {
+            Some(Message::GameAccept { name, code: 0 }) => {
+                Ok(DxpGame::new(connection, settings.side, name, &request))
+            }
+            Some(Message::GameAccept { name, code }) => {
+                Err(format!("{} refused the game with code {}", name, code))
+            }
+            Some(message) => Err(format!("Expected a game acceptance, got {:?}", message)),
+            None => Err(String::from("The game request was not answered")),
+        }
+    }
+
+    /// Waits for the peer at the other end of `stream` to ask for a game, and accepts it.
+    pub fn follow(stream: TcpStream, name: &str) -> Result<DxpGame, String> {
+        let mut connection = Connection::new(stream)?;
+        let request = loop {
+            if let Some(message) = connection.receive(ANSWER_TIMEOUT)? {
+                break message;
+            }
+        };
+
+        let (version, follower, peer_name) = match &request {
+            Message::GameRequest {
+                version,
+                follower,
+                name: peer_name,
+                ..
+            } => (*version, *follower, peer_name.clone()),
+            _ => return Err(format!("Expected a game request, got {:?}", request)),
+        };
+        if version != VERSION {
+            connection.send(&Message::GameAccept {
+                name: String::from(name),
+                code: 1,
+            })?;
+            return Err(format!("{} speaks DXP version {}", peer_name, version));
+        }
+
+        connection.send(&Message::GameAccept {
+            name: String::from(name),
+            code: 0,
+        })?;
+        Ok(DxpGame::new(connection, follower, peer_name, &request))
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn peer_name(&self) -> &str {
+        &self.peer_name
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Chat messages received so far.
+    pub fn chat(&self) -> &[String] {
+        &self.chat
+    }
+
+    /// The result as known on the board, or as the peer told it when the game ended early.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.game.outcome().or(self.peer_result)
+    }
+
+    pub fn send_chat(&mut self, text: &str) -> Result<(), String> {
+        self.connection.send(&Message::Chat(String::from(text)))
+    }
+
+    /// Plays the game to its end.
+    pub fn play(&mut self, worker: &mut Worker) -> Result<Option<Outcome>, String> {
+        while self.play_ply(worker)? {}
+        Ok(self.outcome())
+    }
+
+    /// Plays one move of ours or waits for one of the peer, `false` once the game is over.
+    pub fn play_ply(&mut self, worker: &mut Worker) -> Result<bool, String> {
+        if self.is_finished {
+            return Ok(false);
+        }
+        if self.game.is_over() {
+            self.end_game()?;
+            return Ok(false);
+        }
+
+        if self.game.board().turn() == self.side {
+            self.think(worker)
+        } else {
+            self.wait_for_move()
+        }
+    }
+
+    /// Asks the peer to go back to the position after `ply` plies, `true` when it agreed.
+    pub fn take_back(&mut self, ply: usize) -> Result<bool, String> {
+        if ply > self.game.ply() {
+            return Err(format!("The game has no ply {}", ply));
+        }
+        let first = self.game.start().turn();
+        let turn = if ply % 2 == 1 {
+            first.opponent()
+        } else {
+            first
+        };
+        self.connection.send(&Message::BackRequest {
+            number: ply as u32 / 2 + 1,
+            turn,
+        })?;
+
+        loop {
+            match self.receive()? {
+                Message::BackAccept { code: 0 } => {
+                    self.go_back(ply);
+                    return Ok(true);
+                }
+                Message::BackAccept { .. } => return Ok(false),
+                // the peer moved before it saw the request
+                Message::Move {
+                    from, to, captured, ..
+                } => {
+                    let mv = self.find_move(from, to, &captured)?;
+                    self.game.play(&mv);
+                }
+                message => {
+                    if let Change::Ended = self.handle(message)? {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+    }
+
+    fn think(&mut self, worker: &mut Worker) -> Result<bool, String> {
+        let started = Instant::now();
+        let deadline = started + self.budget();
+        worker.start(&self.game)?;
+
+        loop {
+            if let Some(result) = worker.poll()? {
+                let spent = started.elapsed();
+                self.time_left = self.time_left.saturating_sub(spent);
+                self.send_move(&result.best, spent)?;
+                self.game.play(&result.best);
+                return Ok(true);
+            }
+            if !worker.is_thinking() {
+                return Err(String::from("The engine found no move"));
+            }
+            if Instant::now() >= deadline {
+                worker.stop();
+            }
+
+            if let Some(message) = self.connection.receive(POLL_INTERVAL)? {
+                match self.handle(message)? {
+                    Change::Nothing => {}
+                    // the search is for a position that is gone
+                    Change::Position => {
+                        worker.cancel();
+                        return Ok(true);
+                    }
+                    Change::Ended => {
+                        worker.cancel();
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+    }
+
+    fn wait_for_move(&mut self) -> Result<bool, String> {
+        loop {
+            match self.receive()? {
+                Message::Move {
+                    from, to, captured, ..
+                } => {
+                    let mv = self.find_move(from, to, &captured)?;
+                    self.game.play(&mv);
+                    return Ok(true);
+                }
+                message => match self.handle(message)? {
+                    Change::Nothing => {}
+                    Change::Position => return Ok(true),
+                    Change::Ended => return Ok(false),
+                },
+            }
+        }
+    }
+
+    // everything but moves, which are only expected at certain times
+    fn handle(&mut self, message: Message) -> Result<Change, String> {
+        match message {
+            Message::Chat(text) => {
+                self.chat.push(text);
+                Ok(Change::Nothing)
+            }
+            Message::BackRequest { number, turn } => {
+                let first = self.game.start().turn();
+                let ply = (number.max(1) as usize - 1) * 2 + (turn != first) as usize;
+                if ply > self.game.ply() {
+                    self.connection.send(&Message::BackAccept { code: 2 })?;
+                    return Ok(Change::Nothing);
+                }
+                self.go_back(ply);
+                self.connection.send(&Message::BackAccept { code: 0 })?;
+                Ok(Change::Position)
+            }
+            Message::GameEnd { reason, .. } => {
+                self.peer_result = match reason {
+                    EndReason::Loss => Some(Outcome::Win(self.side)),
+                    EndReason::Win => Some(Outcome::Win(self.side.opponent())),
+                    EndReason::Draw => Some(Outcome::Draw(DrawReason::Agreement)),
+                    EndReason::Unknown => None,
+                };
+                if !self.sent_end {
+                    self.send_end()?;
+                }
+                self.is_finished = true;
+                Ok(Change::Ended)
+            }
+            Message::Move { .. } => Err(String::from("The peer moved out of turn")),
+            _ => Ok(Change::Nothing),
+        }
+    }
+
+    // tells the peer the game is over and waits for it to agree
+    fn end_game(&mut self) -> Result<(), String> {
+        if !self.sent_end {
+            self.send_end()?;
+        }
+        let deadline = Instant::now() + ANSWER_TIMEOUT;
+        while !self.is_finished && Instant::now() < deadline {
+            match self.connection.receive(POLL_INTERVAL) {
+                Ok(Some(message @ Message::GameEnd { .. })) => {
+                    self.handle(message)?;
+                }
+                Ok(_) => {}
+                // the peer may hang up as soon as it has answered
+                Err(_) => break,
+            }
+        }
+        self.is_finished = true;
+        Ok(())
+    }
+
+    fn send_end(&mut self) -> Result<(), String> {
+        let reason = match self.game.outcome() {
+            Some(Outcome::Win(side)) if side == self.side => EndReason::Win,
+            Some(Outcome::Win(_)) => EndReason::Loss,
+            Some(Outcome::Draw(_)) => EndReason::Draw,
+            None => EndReason::Unknown,
+        };
+        self.sent_end = true;
+        self.connection
+            .send(&Message::GameEnd { reason, stop: true })
+    }
+
+    fn send_move(&mut self, mv: &Move, spent: Duration) -> Result<(), String> {
+        let square = |pos: Position| pos.square().unwrap_or(0);
+        self.connection.send(&Message::Move {
+            seconds: spent.as_secs() as u32,
+            from: square(mv.from),
+            to: square(mv.to()),
+            captured: mv.captured.iter().map(|(pos, _)| square(*pos)).collect(),
+        })
+    }
+
+    fn receive(&mut self) -> Result<Message, String> {
+        loop {
+            if let Some(message) = self.connection.receive(ANSWER_TIMEOUT)? {
+                return Ok(message);
+            }
+        }
+    }
+
+    fn find_move(&self, from: usize, to: usize, captured: &[usize]) -> Result<Move, String> {
+        self.game
+            .board()
+            .legal_moves()
+            .into_iter()
+            .find(|mv| {
+                mv.from.square() == Some(from)
+                    && mv.to().square() == Some(to)
+                    && mv.captured.len() == captured.len()
+                    && mv.captured.iter().all(|(pos, _)| {
+                        pos.square()
+                            .is_some_and(|square| captured.contains(&square))
+                    })
+            })
+            .ok_or_else(|| format!("The peer played an illegal move {}-{}", from, to))
+    }
+
+    fn go_back(&mut self, ply: usize) {
+        while self.game.ply() > ply && self.game.undo() {}
+    }
+
+    // the time left spread over the moves still to come in this time control
+    fn budget(&self) -> Duration {
+        let played = self
+            .game
+            .moves()
+            .enumerate()
+            .filter(|(ply, _)| (*ply % 2 == 0) == (self.game.start().turn() == self.side))
+            .count() as u32;
+        let moves_to_go = if played < self.moves {
+            self.moves - played
+        } else {
+            DEFAULT_MOVES_TO_GO
+        };
+        (self.time_left / moves_to_go).min(self.time_left / 2)
+    }
+}
@@ -0,0 +1,278 @@
+use crate::rules::{Board, Piece, Position, Side, BOARD_SIZE};
+use std::fmt;
+
+const SQUARES: usize = BOARD_SIZE / 2;
+const NAME_LENGTH: usize = 32;
+pub const VERSION: u32 = 1;
+
+/// Why a game ended, from the point of view of the side sending `GameEnd`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EndReason {
+    Unknown,
+    Loss,
+    Draw,
+    Win,
+}
+
+/// The messages of the DamExchange protocol. Each one is sent as text starting with a letter
+/// for its kind, fields of fixed width, and a closing zero byte.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Message {
+    GameRequest {
+        version: u32,
+        name: String,
+        /// The side the follower plays.
+        follower: Side,
+        minutes: u32,
+        moves: u32,
+        /// `None` for the usual starting position.
+        start: Option<Board>,
+    },
+    GameAccept {
+        name: String,
+        /// 0 when accepted, otherwise why not.
+        code: u32,
+    },
+    Move {
+        seconds: u32,
+        from: usize,
+        to: usize,
+        captured: Vec<usize>,
+    },
+    GameEnd {
+        reason: EndReason,
+        /// Whether the sender does not want to play another game.
+        stop: bool,
+    },
+    Chat(String),
+    BackRequest {
+        /// The move to go back to, counting from 1, and the side to move there.
+        number: u32,
+        turn: Side,
+    },
+    BackAccept {
+        /// 0 when accepted, 1 when taking back is not supported, 2 when refused.
+        code: u32,
+    },
+}
+
+impl Message {
+    pub fn parse(text: &str) -> Result<Message, String> {
+        let mut fields = Fields {
+            text,
+            rest: text.get(1..).unwrap_or(""),
+        };
+        match text.chars().next() {
+            Some('R') => {
+                let version = fields.number(2)?;
+                let name = fields.name()?;
+                let follower = fields.side()?;
+                let minutes = fields.number(3)?;
+                let moves = fields.number(3)?;
+                let start = match fields.take(1)? {
+                    "A" => None,
+                    "B" => Some(fields.board()?),
+                    _ => return Err(fields.invalid()),
+                };
+                Ok(Message::GameRequest {
+                    version,
+                    name,
+                    follower,
+                    minutes,
+                    moves,
+                    start,
+                })
+            }
+            Some('A') => Ok(Message::GameAccept {
+                name: fields.name()?,
+                code: fields.number(1)?,
+            }),
+            Some('M') => {
+                let seconds = fields.number(4)?;
+                let from = fields.number(2)? as usize;
+                let to = fields.number(2)? as usize;
+                let count = fields.number(2)?;
+                let captured = (0..count)
+                    .map(|_| fields.number(2).map(|square| square as usize))
+                    .collect::<Result<Vec<usize>, String>>()?;
+                Ok(Message::Move {
+                    seconds,
+                    from,
+                    to,
+                    captured,
+                })
+            }
+            Some('E') => {
+                let reason = match fields.number(1)? {
+                    1 => EndReason::Loss,
+                    2 => EndReason::Draw,
+                    3 => EndReason::Win,
+                    _ => EndReason::Unknown,
+                };
+                Ok(Message::GameEnd {
+                    reason,
+                    stop: fields.number(1)? == 1,
+                })
+            }
+            Some('C') => Ok(Message::Chat(String::from(fields.rest))),
+            Some('B') => Ok(Message::BackRequest {
+                number: fields.number(3)?,
+                turn: fields.side()?,
+            }),
+            Some('K') => Ok(Message::BackAccept {
+                code: fields.number(1)?,
+            }),
+            _ => Err(format!("Unknown message '{}'", text)),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::GameRequest {
+                version,
+                name,
+                follower,
+                minutes,
+                moves,
+                start,
+            } => {
+                write!(
+                    f,
+                    "R{:02}{}{}{:03}{:03}",
+                    version,
+                    padded(name),
+                    side_letter(*follower),
+                    minutes,
+                    moves
+                )?;
+                match start {
+                    Some(board) => write!(f, "B{}", write_board(board)),
+                    None => write!(f, "A"),
+                }
+            }
+            Message::GameAccept { name, code } => write!(f, "A{}{}", padded(name), code),
+            Message::Move {
+                seconds,
+                from,
+                to,
+                captured,
+            } => {
+                write!(
+                    f,
+                    "M{:04}{:02}{:02}{:02}",
+                    seconds,
+                    from,
+                    to,
+                    captured.len()
+                )?;
+                for square in captured {
+                    write!(f, "{:02}", square)?;
+                }
+                Ok(())
+            }
+            Message::GameEnd { reason, stop } => {
+                let reason = match reason {
+                    EndReason::Unknown => 0,
+                    EndReason::Loss => 1,
+                    EndReason::Draw => 2,
+                    EndReason::Win => 3,
+                };
+                write!(f, "E{}{}", reason, *stop as u8)
+            }
+            Message::Chat(text) => write!(f, "C{}", text),
+            Message::BackRequest { number, turn } => {
+                write!(f, "B{:03}{}", number, side_letter(*turn))
+            }
+            Message::BackAccept { code } => write!(f, "K{}", code),
+        }
+    }
+}
+
+// white is red and black, zwart in Dutch, is green
+fn side_letter(side: Side) -> char {
+    match side {
+        Side::Green => 'Z',
+        Side::Red => 'W',
+    }
+}
+
+fn padded(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(char::is_ascii)
+        .take(NAME_LENGTH)
+        .collect();
+    format!("{:<width$}", name, width = NAME_LENGTH)
+}
+
+// the side to move followed by one letter per square
+fn write_board(board: &Board) -> String {
+    let mut text = side_letter(board.turn()).to_string();
+    for square in 1..=SQUARES {
+        let piece = Position::from_square(square).and_then(|pos| board.piece_at(pos));
+        text.push(match piece {
+            Some(piece) if piece == Piece::man(Side::Green) => 'z',
+            Some(piece) if piece == Piece::man(Side::Red) => 'w',
+            Some(piece) if piece.side == Side::Green => 'Z',
+            Some(_) => 'W',
+            None => 'e',
+        });
+    }
+    text
+}
+
+// reads fixed width fields off the front of a message
+struct Fields<'a> {
+    text: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Fields<'a> {
+    fn invalid(&self) -> String {
+        format!("Invalid message '{}'", self.text)
+    }
+
+    fn take(&mut self, width: usize) -> Result<&'a str, String> {
+        if self.rest.len() < width || !self.rest.is_char_boundary(width) {
+            return Err(self.invalid());
+        }
+        let (field, rest) = self.rest.split_at(width);
+        self.rest = rest;
+        Ok(field)
+    }
+
+    fn number(&mut self, width: usize) -> Result<u32, String> {
+        let field = self.take(width)?;
+        field.trim().parse().map_err(|_| self.invalid())
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        Ok(String::from(self.take(NAME_LENGTH)?.trim_end()))
+    }
+
+    fn side(&mut self) -> Result<Side, String> {
+        match self.take(1)? {
+            "Z" => Ok(Side::Green),
+            "W" => Ok(Side::Red),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn board(&mut self) -> Result<Board, String> {
+        let mut board = Board::empty(self.side()?);
+        for square in 1..=SQUARES {
+            let piece = match self.take(1)? {
+                "z" => Piece::man(Side::Green),
+                "w" => Piece::man(Side::Red),
+                "Z" => Piece::king(Side::Green),
+                "W" => Piece::king(Side::Red),
+                "e" => continue,
+                _ => return Err(self.invalid()),
+            };
+            board.set_piece(Position::from_square(square).unwrap(), Some(piece));
+        }
+        Ok(board)
+    }
+}
@@ -0,0 +1,11 @@
+//! The DamExchange protocol (DXP) for playing engines against each other over TCP. The side that
+//! connects asks for a game with `GAMEREQ`, the side that listens answers with `GAMEACC`, and from
+//! then on both send their moves, take-back requests and the end of the game.
+
+mod connection;
+mod game;
+mod message;
+
+pub use connection::Connection;
+pub use game::{DxpGame, GameSettings};
+pub use message::{EndReason, Message, VERSION};
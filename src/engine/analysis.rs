@@ -0,0 +1,130 @@
+use crate::engine::{Searcher, MAN_VALUE};
+use crate::rules::{Game, Move};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// how much worse than the engine's choice a move may score before it is flagged
+const MISTAKE_DROP: i32 = MAN_VALUE / 2;
+const BLUNDER_DROP: i32 = MAN_VALUE * 3 / 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Judgement {
+    Good,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    fn of(drop: i32) -> Judgement {
+        if drop >= BLUNDER_DROP {
+            Judgement::Blunder
+        } else if drop >= MISTAKE_DROP {
+            Judgement::Mistake
+        } else {
+            Judgement::Good
+        }
+    }
+}
+
+/// A played move next to the move the engine prefers in the same position.
+#[derive(Debug, Clone)]
+pub struct MoveReview {
+    pub played: Move,
+    pub best: Move,
+    /// Both scores are from the point of view of the side that moved.
+    pub played_score: i32,
+    pub best_score: i32,
+    pub judgement: Judgement,
+}
+
+impl MoveReview {
+    /// How much the evaluation dropped by playing this move instead of the best one.
+    pub fn drop(&self) -> i32 {
+        (self.best_score - self.played_score).max(0)
+    }
+}
+
+/// Searches every position of `game` to review the move played there, calling `progress` after
+/// each one. Stops early, with the moves reviewed so far, once `stop` is set.
+pub fn analyse<F: FnMut(&MoveReview)>(
+    game: &Game,
+    searcher: &mut Searcher,
+    stop: &AtomicBool,
+    mut progress: F,
+) -> Vec<MoveReview> {
+    let mut reviews = vec![];
+    // replayed from the start, keeping the game's draw rules
+    let mut position = game.clone();
+    while position.undo() {}
+
+    for played in game.moves() {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let best = match searcher.search(&position, stop) {
+            Some(best) => best,
+            None => break,
+        };
+
+        // the engine's own choice keeps its score, anything else is searched on its own
+        let played_score = if best.best == *played {
+            best.score
+        } else {
+            match searcher.search_move(&position, played, stop) {
+                Some(result) => result.score,
+                None => break,
+            }
+        };
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let review = MoveReview {
+            played: played.clone(),
+            best: best.best,
+            played_score,
+            best_score: best.score,
+            judgement: Judgement::of(best.score - played_score),
+        };
+        progress(&review);
+        reviews.push(review);
+
+        position.play(played);
+    }
+    reviews
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{parse_fen, parse_move, write_move};
+
+    #[test]
+    fn a_move_losing_the_game_is_a_blunder() {
+        // 9-14 walks into 18x9, 9-13 would have kept the game going
+        let mut game = Game::new(parse_fen("B:W18:B9").unwrap());
+        for text in ["9-14", "18x9"] {
+            let mv = parse_move(game.board(), text).unwrap();
+            game.play(&mv);
+        }
+
+        let mut searcher = Searcher::new(6);
+        let mut seen = 0;
+        let reviews = analyse(&game, &mut searcher, &AtomicBool::new(false), |_| seen += 1);
+        assert_eq!(reviews.len(), 2);
+        assert_eq!(seen, 2);
+
+        assert_eq!(reviews[0].judgement, Judgement::Blunder);
+        assert_eq!(write_move(&reviews[0].best), "9-13");
+        assert!(reviews[0].drop() >= BLUNDER_DROP);
+        assert_eq!(reviews[1].judgement, Judgement::Good);
+    }
+
+    #[test]
+    fn judges_by_how_far_the_score_dropped() {
+        assert_eq!(Judgement::of(0), Judgement::Good);
+        assert_eq!(Judgement::of(MISTAKE_DROP - 1), Judgement::Good);
+        assert_eq!(Judgement::of(MISTAKE_DROP), Judgement::Mistake);
+        assert_eq!(Judgement::of(BLUNDER_DROP - 1), Judgement::Mistake);
+        assert_eq!(Judgement::of(BLUNDER_DROP), Judgement::Blunder);
+    }
+}
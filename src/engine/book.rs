@@ -0,0 +1,251 @@
+use crate::engine::Random;
+use crate::notation::{parse_fen, parse_move, write_fen, write_move};
+use crate::rules::{Board, Game, Move};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    fen: String,
+    /// Moves in numeric notation with how often they should be picked.
+    moves: Vec<(String, u32)>,
+}
+
+/// Opening moves by position. The file has one move per line: the position as FEN, the move
+/// and its weight, so books can be written by hand as well as built from games.
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: HashMap<u64, Entry>,
+}
+
+impl Book {
+    pub fn new() -> Book {
+        Book::default()
+    }
+
+    pub fn load(path: &Path) -> Result<Book, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Book::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Book, String> {
+        let mut book = Book::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let at_line = |e: String| format!("{} on line {}", e, number + 1);
+            let mut fields = line.split_whitespace();
+            let (fen, text) = match (fields.next(), fields.next()) {
+                (Some(fen), Some(text)) => (fen, text),
+                _ => return Err(at_line(String::from("Missing move"))),
+            };
+            let weight = match fields.next() {
+                Some(weight) => weight
+                    .parse()
+                    .map_err(|_| at_line(format!("Invalid weight '{}'", weight)))?,
+                None => 1,
+            };
+
+            let board = parse_fen(fen).map_err(at_line)?;
+            let mv = parse_move(&board, text).map_err(at_line)?;
+            book.add(&board, &mv, weight);
+        }
+        Ok(book)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds `weight` to the move played in `board`.
+    pub fn add(&mut self, board: &Board, mv: &Move, weight: u32) {
+        let entry = self.entries.entry(board.key()).or_insert_with(|| Entry {
+            fen: write_fen(board),
+            moves: vec![],
+        });
+
+        let text = write_move(mv);
+        match entry.moves.iter_mut().find(|(known, _)| *known == text) {
+            Some((_, known_weight)) => *known_weight = known_weight.saturating_add(weight),
+            None => entry.moves.push((text, weight)),
+        }
+    }
+
+    /// Counts the first `plies` moves of `game` towards the book.
+    pub fn add_game(&mut self, game: &Game, plies: usize) {
+        let mut board = *game.start();
+        for mv in game.moves().take(plies) {
+            self.add(&board, mv, 1);
+            board.apply(mv);
+        }
+    }
+
+    /// Drops moves seen less than `weight` times, and positions left without moves.
+    pub fn prune(&mut self, weight: u32) {
+        for entry in self.entries.values_mut() {
+            entry.moves.retain(|(_, known)| *known >= weight);
+        }
+        self.entries.retain(|_, entry| !entry.moves.is_empty());
+    }
+
+    pub fn contains(&self, board: &Board) -> bool {
+        self.entries.contains_key(&board.key())
+    }
+
+    /// The book moves of `board` with their weights.
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u32)> {
+        match self.entries.get(&board.key()) {
+            Some(entry) => entry
+                .moves
+                .iter()
+                .filter_map(|(text, weight)| parse_move(board, text).ok().map(|mv| (mv, *weight)))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// A book move picked at random, moves with more weight being picked more often.
+    pub fn pick(&self, board: &Board, random: &mut Random) -> Option<Move> {
+        let moves = self.moves(board);
+        // summed wider, the weights of a position can add up past `u32::MAX`
+        let total: u64 = moves.iter().map(|(_, weight)| u64::from(*weight)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut left = random.below(total as usize) as u64;
+        for (mv, weight) in moves {
+            if left < u64::from(weight) {
+                return Some(mv);
+            }
+            left -= u64::from(weight);
+        }
+        None
+    }
+}
+
+impl fmt::Display for Book {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# position, move, weight")?;
+
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.fen.cmp(&b.fen));
+        for entry in entries {
+            let mut moves = entry.moves.clone();
+            moves.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            for (text, weight) in moves {
+                writeln!(f, "{} {} {}", entry.fen, text, weight)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::PdnGame;
+
+    const GAMES: &str = "1. 11-15 23-19 2. 8-11 *\n\n1. 11-15 22-17 *\n\n1. 9-13 22-18 *\n";
+
+    fn from_games() -> Book {
+        let mut book = Book::new();
+        for pdn in PdnGame::parse_all(GAMES).unwrap() {
+            book.add_game(&pdn.to_game().unwrap(), 2);
+        }
+        book
+    }
+
+    fn written(moves: Vec<(Move, u32)>) -> Vec<(String, u32)> {
+        let mut moves: Vec<_> = moves
+            .iter()
+            .map(|(mv, weight)| (write_move(mv), *weight))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn builds_from_games_and_reads_back() {
+        let book = from_games();
+        let start = Board::new();
+        assert_eq!(
+            written(book.moves(&start)),
+            [(String::from("11-15"), 2), (String::from("9-13"), 1)]
+        );
+        // only the first two plies of every game count
+        assert_eq!(book.len(), 3);
+
+        let read = Book::parse(&book.to_string()).unwrap();
+        assert_eq!(read.len(), book.len());
+        assert_eq!(written(read.moves(&start)), written(book.moves(&start)));
+    }
+
+    #[test]
+    fn picks_only_book_moves() {
+        let book = from_games();
+        let start = Board::new();
+        let known = written(book.moves(&start));
+        let mut random = Random::new(3);
+        let mut picked = vec![];
+        for _ in 0..100 {
+            let mv = write_move(&book.pick(&start, &mut random).unwrap());
+            assert!(known.iter().any(|(text, _)| *text == mv), "{}", mv);
+            if !picked.contains(&mv) {
+                picked.push(mv);
+            }
+        }
+        assert_eq!(picked.len(), 2);
+    }
+
+    #[test]
+    fn has_nothing_out_of_the_book() {
+        let book = from_games();
+        let mut random = Random::new(3);
+        let board = parse_fen("B:W18,22:B9,10").unwrap();
+        assert!(!book.contains(&board));
+        assert_eq!(book.pick(&board, &mut random), None);
+    }
+
+    #[test]
+    fn keeps_heavy_weights_in_range() {
+        let start = Board::new();
+        let moves = start.legal_moves();
+        let mut book = Book::new();
+        book.add(&start, &moves[0], u32::MAX);
+        book.add(&start, &moves[0], 1);
+        book.add(&start, &moves[1], u32::MAX);
+        assert_eq!(
+            book.moves(&start)
+                .iter()
+                .map(|(_, weight)| *weight)
+                .collect::<Vec<_>>(),
+            [u32::MAX, u32::MAX]
+        );
+
+        let mut random = Random::new(3);
+        let mut picked = vec![];
+        for _ in 0..100 {
+            let mv = book.pick(&start, &mut random).unwrap();
+            if !picked.contains(&mv) {
+                picked.push(mv);
+            }
+        }
+        assert_eq!(picked.len(), 2);
+    }
+
+    #[test]
+    fn refuses_bad_lines() {
+        assert!(Book::parse("B:W21-32:B1-12").is_err());
+        assert!(Book::parse("B:W21-32:B1-12 11-16x").is_err());
+        assert!(Book::parse("B:W21-32:B1-12 11-15 many").is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::rules::{BitBoard, Piece, Position};
+
+pub const MAN_VALUE: i32 = 100;
+pub const KING_VALUE: i32 = 160;
+
+const BACK_RANK_BONUS: i32 = 12;
+const CENTRE_BONUS: i32 = 6;
+const ADVANCE_BONUS: i32 = 3;
+
+/// Static score of `board` from the point of view of the side to move.
+pub fn evaluate(board: &BitBoard) -> i32 {
+    let turn = board.turn();
+    board
+        .pieces()
+        .map(|(pos, piece)| {
+            let value = piece_value(pos, piece);
+            if piece.side == turn {
+                value
+            } else {
+                -value
+            }
+        })
+        .sum()
+}
+
+fn piece_value(pos: Position, piece: Piece) -> i32 {
+    let mut value = if piece.is_king() {
+        KING_VALUE
+    } else {
+        MAN_VALUE
+    };
+
+    let is_centre = (2..=5).contains(&pos.column()) && (3..=4).contains(&pos.row());
+    if is_centre {
+        value += CENTRE_BONUS;
+    }
+
+    if !piece.is_king() {
+        // men left on the back rank keep the opponent from crowning
+        let back_row = piece.side.opponent().promotion_row();
+        let advanced = (pos.row() as i32 - back_row as i32).abs();
+        if advanced == 0 {
+            value += BACK_RANK_BONUS;
+        }
+        value += advanced * ADVANCE_BONUS;
+    }
+
+    value
+}
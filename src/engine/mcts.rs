@@ -0,0 +1,294 @@
+use crate::engine::{evaluate, Engine, Random, SearchResult, MAN_VALUE};
+use crate::rules::{BitBoard, Game, Move, Side};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const DEFAULT_EXPLORATION: f64 = 1.4;
+// playouts still going after this many plies are judged by material
+const MAX_PLAYOUT_PLIES: usize = 150;
+const PLAYOUT_ADVANTAGE: i32 = MAN_VALUE;
+// scale of the reported score, a certain win is worth this much
+const SCORE_SCALE: f64 = 1000.0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Playout {
+    /// Every legal move is equally likely.
+    Random,
+    /// Crowning moves and the longest captures are played whenever there are any.
+    Guided,
+}
+
+struct Node {
+    mv: Option<Move>,
+    /// The side that played `mv`, the rewards are counted for it.
+    mover: Side,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // filled in on the second visit, so leaves stay small
+    untried: Option<Vec<Move>>,
+    visits: u32,
+    reward: f64,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, mover: Side, parent: Option<usize>) -> Node {
+        Node {
+            mv,
+            mover,
+            parent,
+            children: vec![],
+            untried: None,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+}
+
+/// Monte Carlo tree search using UCT to pick which line to play out next.
+pub struct Mcts {
+    iterations: u32,
+    time_limit: Option<Duration>,
+    exploration: f64,
+    playout: Playout,
+    random: Random,
+}
+
+impl Mcts {
+    pub fn new(iterations: u32) -> Mcts {
+        Mcts {
+            iterations: iterations.max(1),
+            time_limit: None,
+            exploration: DEFAULT_EXPLORATION,
+            playout: Playout::Guided,
+            random: Random::from_time(),
+        }
+    }
+
+    /// Stops after `time_limit` even when not all iterations have been run.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Mcts {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Mcts {
+        self.random = Random::new(seed);
+        self
+    }
+
+    pub fn with_playout(mut self, playout: Playout) -> Mcts {
+        self.playout = playout;
+        self
+    }
+
+    /// Higher values try rarely visited moves more often.
+    pub fn with_exploration(mut self, exploration: f64) -> Mcts {
+        self.exploration = exploration;
+        self
+    }
+
+    fn select_child(&self, nodes: &[Node], parent: usize) -> usize {
+        let log_visits = (nodes[parent].visits as f64).ln();
+        let uct = |child: &Node| {
+            let visits = child.visits as f64;
+            child.reward / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+
+        let mut children = nodes[parent].children.iter();
+        let mut best = *children.next().expect("an expanded node has children");
+        for &child in children {
+            if uct(&nodes[child]) > uct(&nodes[best]) {
+                best = child;
+            }
+        }
+        best
+    }
+
+    /// Plays random moves until the game ends, `None` for a draw.
+    fn play_out(&mut self, board: &mut BitBoard) -> Option<Side> {
+        for _ in 0..MAX_PLAYOUT_PLIES {
+            let moves = board.legal_moves();
+            if moves.is_empty() {
+                return Some(board.turn().opponent());
+            }
+            let mv = &moves[self.choose(&moves)];
+            board.apply(mv);
+        }
+
+        match evaluate(board) {
+            score if score >= PLAYOUT_ADVANTAGE => Some(board.turn()),
+            score if score <= -PLAYOUT_ADVANTAGE => Some(board.turn().opponent()),
+            _ => None,
+        }
+    }
+
+    fn choose(&mut self, moves: &[Move]) -> usize {
+        if self.playout == Playout::Guided {
+            let crowning: Vec<usize> = (0..moves.len()).filter(|&i| moves[i].promotes).collect();
+            if !crowning.is_empty() {
+                return crowning[self.random.below(crowning.len())];
+            }
+
+            let longest = moves.iter().map(Move::capture_count).max().unwrap_or(0);
+            let greedy: Vec<usize> = (0..moves.len())
+                .filter(|&i| moves[i].capture_count() == longest)
+                .collect();
+            return greedy[self.random.below(greedy.len())];
+        }
+        self.random.below(moves.len())
+    }
+}
+
+impl Mcts {
+    fn run(&mut self, game: &Game, stop: &AtomicBool) -> Option<SearchResult> {
+        let root_board = BitBoard::from(game.board());
+        let mut moves = root_board.legal_moves();
+        if moves.len() <= 1 {
+            return moves.pop().map(|best| SearchResult {
+                best,
+                score: 0,
+                depth: 0,
+                nodes: 0,
+            });
+        }
+
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let mut root = Node::new(None, root_board.turn().opponent(), None);
+        root.untried = Some(moves);
+        let mut nodes = vec![root];
+        let mut max_depth = 0;
+        let mut iterations = 0;
+
+        while iterations < self.iterations {
+            let is_late = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if is_late || stop.load(Ordering::Relaxed) {
+                break;
+            }
+            iterations += 1;
+
+            let mut board = root_board;
+            let mut node = 0;
+            let mut depth = 0;
+
+            // follow the most promising moves down to a node with moves left to try
+            loop {
+                if nodes[node].untried.is_none() && nodes[node].visits > 0 {
+                    nodes[node].untried = Some(board.legal_moves());
+                }
+                let has_untried = match &nodes[node].untried {
+                    Some(untried) => !untried.is_empty(),
+                    None => false,
+                };
+                if has_untried || nodes[node].children.is_empty() {
+                    break;
+                }
+
+                node = self.select_child(&nodes, node);
+                if let Some(mv) = &nodes[node].mv {
+                    board.apply(mv);
+                }
+                depth += 1;
+            }
+
+            // add one untried move to the tree
+            let next = match nodes[node].untried.as_mut() {
+                Some(untried) if !untried.is_empty() => {
+                    let index = self.random.below(untried.len());
+                    Some(untried.swap_remove(index))
+                }
+                _ => None,
+            };
+            if let Some(mv) = next {
+                let mover = board.turn();
+                board.apply(&mv);
+                nodes.push(Node::new(Some(mv), mover, Some(node)));
+                let child = nodes.len() - 1;
+                nodes[node].children.push(child);
+                node = child;
+                depth += 1;
+            }
+            max_depth = max_depth.max(depth);
+
+            let winner = self.play_out(&mut board);
+
+            let mut current = Some(node);
+            while let Some(index) = current {
+                let node = &mut nodes[index];
+                node.visits += 1;
+                node.reward += match winner {
+                    Some(side) if side == node.mover => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                };
+                current = node.parent;
+            }
+        }
+
+        // the most visited move is the one the search trusts most
+        let best = nodes[0]
+            .children
+            .iter()
+            .copied()
+            .max_by_key(|&child| nodes[child].visits)?;
+        let best = &nodes[best];
+        let win_rate = best.reward / best.visits as f64;
+
+        Some(SearchResult {
+            best: best.mv.clone()?,
+            score: ((win_rate * 2.0 - 1.0) * SCORE_SCALE) as i32,
+            depth: max_depth,
+            nodes: iterations as u64,
+        })
+    }
+}
+
+impl Engine for Mcts {
+    fn search(&mut self, game: &Game, stop: &AtomicBool) -> Result<Option<SearchResult>, String> {
+        Ok(self.run(game, stop))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{parse_fen, write_move};
+
+    #[test]
+    fn keeps_away_from_a_losing_capture() {
+        // after 9-14 the red man takes the last green piece with 18x9
+        let game = Game::new(parse_fen("B:W18:B9").unwrap());
+        let result = Mcts::new(500)
+            .with_seed(5)
+            .run(&game, &AtomicBool::new(false))
+            .unwrap();
+        assert_eq!(write_move(&result.best), "9-13");
+    }
+
+    #[test]
+    fn always_answers_with_a_legal_move() {
+        let mut random = Random::new(13);
+        let mut mcts = Mcts::new(200).with_seed(13);
+        let stop = AtomicBool::new(false);
+        let mut game = Game::default();
+        while !game.is_over() && game.ply() < 60 {
+            let result = mcts.run(&game, &stop).unwrap();
+            let moves = game.board().legal_moves();
+            assert!(moves.contains(&result.best), "{:?}", result.best);
+
+            let mv = moves[random.below(moves.len())].clone();
+            game.play(&mv);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_move() {
+        let stop = AtomicBool::new(false);
+        let mut game = Game::default();
+        for _ in 0..6 {
+            let first = Mcts::new(500).with_seed(21).run(&game, &stop).unwrap();
+            let second = Mcts::new(500).with_seed(21).run(&game, &stop).unwrap();
+            assert_eq!(first.best, second.best);
+            assert_eq!(first.score, second.score);
+            game.play(&first.best);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+mod analysis;
+mod book;
+mod eval;
+mod mcts;
+mod random;
+mod search;
+mod table;
+mod worker;
+
+pub use analysis::{analyse, Judgement, MoveReview};
+pub use book::Book;
+pub use eval::{evaluate, KING_VALUE, MAN_VALUE};
+pub use mcts::{Mcts, Playout};
+pub use random::Random;
+pub use search::{is_mate_score, SearchResult, Searcher, MATE_SCORE};
+pub use table::{Bound, Entry, TranspositionTable};
+pub use worker::Worker;
+
+use crate::rules::Game;
+use std::sync::atomic::AtomicBool;
+
+/// Anything that can pick a move for the side to move, searching until it is done or `stop` is set.
+pub trait Engine: Send {
+    /// The move found, `None` when there is none to play, an error when the engine failed.
+    fn search(&mut self, game: &Game, stop: &AtomicBool) -> Result<Option<SearchResult>, String>;
+
+    /// Limits later searches to `depth` plies, for engines that search by depth.
+    fn set_max_depth(&mut self, _depth: u32) {}
+}
@@ -0,0 +1,357 @@
+use crate::engine::{evaluate, Bound, Engine, TranspositionTable};
+use crate::rules::{BitBoard, Game, Move};
+use crate::tablebase::{Tablebase, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const MATE_SCORE: i32 = 100_000;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+const MAX_PLY: usize = 128;
+// nodes searched between looking at the clock and the stop flag
+const CHECK_INTERVAL: u64 = 1024;
+const DEFAULT_TABLE_SIZE: usize = 16;
+// tablebase wins rank below found mates, as they are measured from the probed position
+const TABLEBASE_WIN: i32 = MATE_SCORE - 1000;
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best: Move,
+    /// From the point of view of the side to move.
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Alpha-beta searcher with iterative deepening.
+pub struct Searcher {
+    max_depth: u32,
+    time_limit: Option<Duration>,
+    deadline: Option<Instant>,
+    aborted: bool,
+    nodes: u64,
+    killers: Vec<[Option<Move>; 2]>,
+    table: TranspositionTable,
+    tablebase: Option<Arc<Tablebase>>,
+}
+
+impl Searcher {
+    pub fn new(max_depth: u32) -> Searcher {
+        Searcher {
+            max_depth: max_depth.max(1),
+            time_limit: None,
+            deadline: None,
+            aborted: false,
+            nodes: 0,
+            killers: vec![[None, None]; MAX_PLY],
+            table: TranspositionTable::new(DEFAULT_TABLE_SIZE),
+            tablebase: None,
+        }
+    }
+
+    /// Stops deepening once `time_limit` has passed, keeping the deepest finished iteration.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Searcher {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Gives the transposition table about `megabytes` of memory, 16 by default.
+    pub fn with_table_size(mut self, megabytes: usize) -> Searcher {
+        self.table = TranspositionTable::new(megabytes);
+        self
+    }
+
+    /// Plays endgames the tablebase knows perfectly and stops searching where it knows the result.
+    pub fn with_tablebase(mut self, tablebase: Arc<Tablebase>) -> Searcher {
+        self.tablebase = Some(tablebase);
+        self
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Searches the position until the depth or time limit is reached, or `stop` is set.
+    pub fn search(&mut self, game: &Game, stop: &AtomicBool) -> Option<SearchResult> {
+        let mut board = BitBoard::from(game.board());
+        let mut moves = board.legal_moves();
+        self.prepare();
+
+        if let Some(tablebase) = &self.tablebase {
+            if let Some((best, value)) = tablebase.best_move(&board) {
+                return Some(SearchResult {
+                    best,
+                    score: tablebase_score(value),
+                    depth: 0,
+                    nodes: 0,
+                });
+            }
+        }
+
+        if moves.len() <= 1 {
+            return moves.pop().map(|best| SearchResult {
+                best,
+                score: evaluate(&board),
+                depth: 0,
+                nodes: 0,
+            });
+        }
+
+        self.deepen(&mut board, moves, stop)
+    }
+
+    /// Searches only `mv`, for the score of a move that was played instead of the best one.
+    pub fn search_move(
+        &mut self,
+        game: &Game,
+        mv: &Move,
+        stop: &AtomicBool,
+    ) -> Option<SearchResult> {
+        let mut board = BitBoard::from(game.board());
+        self.prepare();
+        self.deepen(&mut board, vec![mv.clone()], stop)
+    }
+
+    fn prepare(&mut self) {
+        self.nodes = 0;
+        self.aborted = false;
+        self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.killers
+            .iter_mut()
+            .for_each(|killers| *killers = [None, None]);
+        self.table.new_search();
+    }
+
+    // iterative deepening over the root `moves`
+    fn deepen(
+        &mut self,
+        board: &mut BitBoard,
+        mut moves: Vec<Move>,
+        stop: &AtomicBool,
+    ) -> Option<SearchResult> {
+        let mut result: Option<SearchResult> = None;
+        for depth in 1..=self.max_depth {
+            self.order_moves(&mut moves, 0, result.as_ref().map(|r| &r.best));
+
+            let mut alpha = -INFINITY;
+            let mut best = moves[0].clone();
+            for mv in &moves {
+                board.apply(mv);
+                let score = -self.alpha_beta(board, depth - 1, 1, -INFINITY, -alpha, stop);
+                board.unapply(mv);
+
+                if self.aborted {
+                    break;
+                }
+                if score > alpha {
+                    alpha = score;
+                    best = mv.clone();
+                }
+            }
+
+            // an unfinished iteration is only trusted when there is nothing better
+            if self.aborted && result.is_some() {
+                break;
+            }
+
+            result = Some(SearchResult {
+                best,
+                score: alpha,
+                depth,
+                nodes: self.nodes,
+            });
+
+            if self.aborted || is_mate_score(alpha) {
+                break;
+            }
+        }
+
+        result
+    }
+
+    fn alpha_beta(
+        &mut self,
+        board: &mut BitBoard,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        stop: &AtomicBool,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.nodes & (CHECK_INTERVAL - 1) == 0 && self.should_stop(stop) {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        let known = self
+            .tablebase
+            .as_ref()
+            .and_then(|tablebase| tablebase.probe(board));
+        if let Some(value) = known {
+            return tablebase_score(value);
+        }
+
+        let key = board.key();
+        let mut table_move = None;
+        if let Some(entry) = self.table.probe(key) {
+            if entry.depth >= depth {
+                let score = entry.score(ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return beta,
+                    Bound::Upper if score <= alpha => return alpha,
+                    _ => {}
+                }
+            }
+            table_move = entry.best.clone();
+        }
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            // the side to move has lost, sooner is worse
+            return -MATE_SCORE + ply as i32;
+        }
+
+        // captures are forced, so only quiet positions are scored
+        let is_quiet = !moves[0].is_capture();
+        if (depth == 0 && is_quiet) || ply >= MAX_PLY - 1 {
+            return evaluate(board);
+        }
+
+        self.order_moves(&mut moves, ply, table_move.as_ref());
+
+        let mut best = None;
+        for mv in &moves {
+            board.apply(mv);
+            let score =
+                -self.alpha_beta(board, depth.saturating_sub(1), ply + 1, -beta, -alpha, stop);
+            board.unapply(mv);
+
+            // scores of an aborted search are made up and must not end up in the table
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                if !mv.is_capture() {
+                    self.store_killer(ply, mv);
+                }
+                self.table
+                    .store(key, depth, ply, beta, Bound::Lower, Some(mv.clone()));
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                best = Some(mv.clone());
+            }
+        }
+
+        let bound = if best.is_some() {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table.store(key, depth, ply, alpha, bound, best);
+        alpha
+    }
+
+    fn should_stop(&self, stop: &AtomicBool) -> bool {
+        let is_late = match self.deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => false,
+        };
+        is_late || stop.load(Ordering::Relaxed)
+    }
+
+    fn store_killer(&mut self, ply: usize, mv: &Move) {
+        let killers = &mut self.killers[ply];
+        if killers[0].as_ref() != Some(mv) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(mv.clone());
+        }
+    }
+
+    // best guess first: the previous iteration's or the table's choice, big captures, crownings,
+    // killers
+    fn order_moves(&self, moves: &mut [Move], ply: usize, first: Option<&Move>) {
+        let killers = &self.killers[ply];
+        moves.sort_by_cached_key(|mv| {
+            let mut key = 0;
+            if Some(mv) == first {
+                key += 1_000_000;
+            }
+            key += mv.capture_count() as i32 * 10_000;
+            if mv.promotes {
+                key += 5_000;
+            }
+            if killers[0].as_ref() == Some(mv) {
+                key += 1_000;
+            } else if killers[1].as_ref() == Some(mv) {
+                key += 900;
+            }
+            -key
+        });
+    }
+}
+
+impl Engine for Searcher {
+    fn search(&mut self, game: &Game, stop: &AtomicBool) -> Result<Option<SearchResult>, String> {
+        Ok(Searcher::search(self, game, stop))
+    }
+
+    fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = depth.max(1);
+    }
+}
+
+fn tablebase_score(value: Value) -> i32 {
+    match value {
+        Value::Win(plies) => TABLEBASE_WIN - plies as i32,
+        Value::Loss(plies) => -TABLEBASE_WIN + plies as i32,
+        Value::Draw => 0,
+    }
+}
+
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_SCORE - MAX_PLY as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Random;
+    use crate::notation::parse_fen;
+    use crate::rules::{Outcome, Side};
+
+    #[test]
+    fn finds_a_win_in_one() {
+        // the red man on 29 is stuck as long as the kings on 25 and 22 stay
+        let mut game = Game::new(parse_fen("B:W29:BK1,K22,K25").unwrap());
+        let result = Searcher::new(6)
+            .search(&game, &AtomicBool::new(false))
+            .unwrap();
+        assert_eq!(result.score, MATE_SCORE - 1);
+
+        game.play(&result.best);
+        assert_eq!(game.outcome(), Some(Outcome::Win(Side::Green)));
+    }
+
+    #[test]
+    fn always_answers_with_a_legal_move() {
+        let mut random = Random::new(11);
+        let mut searcher = Searcher::new(3).with_table_size(1);
+        let stop = AtomicBool::new(false);
+        let mut game = Game::default();
+        while !game.is_over() && game.ply() < 60 {
+            let result = searcher.search(&game, &stop).unwrap();
+            let moves = game.board().legal_moves();
+            assert!(moves.contains(&result.best), "{:?}", result.best);
+
+            let mv = moves[random.below(moves.len())].clone();
+            game.play(&mv);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::engine::is_mate_score;
+use crate::rules::Move;
+use std::mem::size_of;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this high, the search was cut off.
+    Lower,
+    /// The score is at most this high, no move raised alpha.
+    Upper,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    score: i32,
+    pub bound: Bound,
+    pub best: Option<Move>,
+    generation: u8,
+}
+
+impl Entry {
+    /// The stored score, with mate distances counted from the root at `ply` again.
+    pub fn score(&self, ply: usize) -> i32 {
+        match self.score {
+            score if is_mate_score(score) && score > 0 => score - ply as i32,
+            score if is_mate_score(score) => score + ply as i32,
+            score => score,
+        }
+    }
+}
+
+/// Fixed-size table of searched positions indexed by their Zobrist key. Deeper results are kept
+/// over shallower ones, unless they are left over from an earlier search.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// A table taking about `megabytes` of memory, rounded down to a power of two entries.
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let wanted = (megabytes * 1024 * 1024 / size_of::<Option<Entry>>()).max(1);
+        let length = 1 << (usize::BITS - 1 - wanted.leading_zeros());
+        TranspositionTable {
+            entries: vec![None; length],
+            generation: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.generation = 0;
+    }
+
+    /// Marks everything stored so far as old, to be replaced first.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        self.entries[self.slot(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// Stores the result of searching a position `ply` moves away from the root.
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        ply: usize,
+        score: i32,
+        bound: Bound,
+        best: Option<Move>,
+    ) {
+        let generation = self.generation;
+        let slot = self.slot(key);
+        let (is_replaced, best) = match &self.entries[slot] {
+            // a result without a best move keeps the one found before
+            Some(old) if old.key == key => (true, best.or_else(|| old.best.clone())),
+            Some(old) => (old.generation != generation || depth >= old.depth, best),
+            None => (true, best),
+        };
+        if !is_replaced {
+            return;
+        }
+
+        // mates are stored as the distance from this position, not from the root
+        let score = match score {
+            score if is_mate_score(score) && score > 0 => score + ply as i32,
+            score if is_mate_score(score) => score - ply as i32,
+            score => score,
+        };
+        self.entries[slot] = Some(Entry {
+            key,
+            depth,
+            score,
+            bound,
+            best,
+            generation,
+        });
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_deeper_entry_stays() {
+        let mut table = TranspositionTable::new(1);
+        // both keys fall into the same slot
+        let deep = 5;
+        let shallow = deep + table.len() as u64;
+
+        table.store(deep, 8, 0, 10, Bound::Exact, None);
+        table.store(shallow, 3, 0, 20, Bound::Exact, None);
+        assert_eq!(table.probe(deep).map(|entry| entry.depth), Some(8));
+        assert!(table.probe(shallow).is_none());
+
+        table.store(shallow, 9, 0, 20, Bound::Lower, None);
+        assert!(table.probe(deep).is_none());
+        assert_eq!(table.probe(shallow).map(|entry| entry.score(0)), Some(20));
+    }
+
+    #[test]
+    fn entries_of_an_earlier_search_give_way() {
+        let mut table = TranspositionTable::new(1);
+        let deep = 5;
+        let shallow = deep + table.len() as u64;
+
+        table.store(deep, 8, 0, 10, Bound::Exact, None);
+        table.new_search();
+        table.store(shallow, 3, 0, 20, Bound::Upper, None);
+        assert!(table.probe(deep).is_none());
+        assert_eq!(table.probe(shallow).map(|entry| entry.depth), Some(3));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::engine::{Book, Engine, Random, SearchResult};
+use crate::rules::Game;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+type Searched = Result<Option<SearchResult>, String>;
+type Returned = Receiver<(Box<dyn Engine>, Searched)>;
+
+const ENGINE_LOST: &str = "The engine stopped unexpectedly";
+
+struct Running {
+    stop: Arc<AtomicBool>,
+    receiver: Returned,
+}
+
+/// Runs an engine on a background thread so the caller can keep drawing frames.
+/// The engine travels to the thread and back with every search.
+pub struct Worker {
+    engine: Option<Box<dyn Engine>>,
+    running: Option<Running>,
+    // a cancelled search the engine has yet to come back from
+    cancelled: Option<Returned>,
+    // a game to search as soon as the engine is back
+    waiting: Option<Box<Game>>,
+    max_depth: Option<u32>,
+    book: Option<Arc<Book>>,
+    random: Random,
+    // a book move waiting to be picked up
+    ready: Option<SearchResult>,
+}
+
+impl Worker {
+    pub fn new<E: Engine + 'static>(engine: E) -> Worker {
+        Worker {
+            engine: Some(Box::new(engine)),
+            running: None,
+            cancelled: None,
+            waiting: None,
+            max_depth: None,
+            book: None,
+            random: Random::from_time(),
+            ready: None,
+        }
+    }
+
+    /// Plays from `book` without searching while the position is in it.
+    pub fn with_book(mut self, book: Arc<Book>) -> Worker {
+        self.book = Some(book);
+        self
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.running.is_some() || self.ready.is_some() || self.waiting.is_some()
+    }
+
+    /// Starts searching a copy of `game`, unless a search is already running. Fails when the
+    /// engine was lost to an earlier search.
+    pub fn start(&mut self, game: &Game) -> Result<(), String> {
+        if self.is_thinking() {
+            return Ok(());
+        }
+
+        let book_move = match &self.book {
+            Some(book) => book.pick(game.board(), &mut self.random),
+            None => None,
+        };
+        if let Some(best) = book_move {
+            self.ready = Some(SearchResult {
+                best,
+                score: 0,
+                depth: 0,
+                nodes: 0,
+            });
+            return Ok(());
+        }
+
+        self.reclaim();
+        match self.engine.take() {
+            Some(engine) => self.spawn(engine, game),
+            // the search starts once the cancelled one has let go of the engine
+            None if self.cancelled.is_some() => self.waiting = Some(Box::new(game.clone())),
+            None => return Err(String::from(ENGINE_LOST)),
+        }
+        Ok(())
+    }
+
+    fn spawn(&mut self, mut engine: Box<dyn Engine>, game: &Game) {
+        if let Some(depth) = self.max_depth {
+            engine.set_max_depth(depth);
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let game = game.clone();
+        let thread_stop = stop.clone();
+
+        thread::spawn(move || {
+            let result = engine.search(&game, &thread_stop);
+            // the receiving side is gone when the worker was dropped, nothing left to do then
+            let _ = sender.send((engine, result));
+        });
+
+        self.running = Some(Running { stop, receiver });
+    }
+
+    // takes the engine back from a cancelled search that has finished
+    fn reclaim(&mut self) {
+        let returned = match &self.cancelled {
+            Some(cancelled) => cancelled.try_recv(),
+            None => return,
+        };
+        match returned {
+            Ok((engine, _)) => {
+                self.engine = Some(engine);
+                self.cancelled = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.cancelled = None,
+        }
+    }
+
+    /// The result of a finished search, `None` while the search is still going and an error
+    /// when the engine failed.
+    pub fn poll(&mut self) -> Result<Option<SearchResult>, String> {
+        if let Some(result) = self.ready.take() {
+            return Ok(Some(result));
+        }
+        if let Some(game) = self.waiting.take() {
+            self.reclaim();
+            match self.engine.take() {
+                Some(engine) => self.spawn(engine, &game),
+                None if self.cancelled.is_some() => self.waiting = Some(game),
+                None => return Err(String::from(ENGINE_LOST)),
+            }
+        }
+
+        let message = match &self.running {
+            Some(running) => running.receiver.try_recv(),
+            None => return Ok(None),
+        };
+
+        match message {
+            Ok((engine, result)) => {
+                self.engine = Some(engine);
+                self.running = None;
+                result
+            }
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                self.running = None;
+                Err(String::from(ENGINE_LOST))
+            }
+        }
+    }
+
+    /// Asks a running search to finish early, its result can still be polled.
+    pub fn stop(&self) {
+        if let Some(running) = &self.running {
+            running.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Limits the searches started from now on, see `Engine::set_max_depth`.
+    pub fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = Some(depth);
+    }
+
+    /// Stops a running search and throws its result away. The search is left to finish on its
+    /// own, the engine is taken back once it has.
+    pub fn cancel(&mut self) {
+        self.ready = None;
+        self.waiting = None;
+        if let Some(running) = self.running.take() {
+            running.stop.store(true, Ordering::Relaxed);
+            self.cancelled = Some(running.receiver);
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Searcher;
+    use std::time::{Duration, Instant};
+
+    struct Broken;
+
+    impl Engine for Broken {
+        fn search(&mut self, _: &Game, _: &AtomicBool) -> Result<Option<SearchResult>, String> {
+            Err(String::from("broken"))
+        }
+    }
+
+    struct Panicking;
+
+    impl Engine for Panicking {
+        fn search(&mut self, _: &Game, _: &AtomicBool) -> Result<Option<SearchResult>, String> {
+            panic!("lost")
+        }
+    }
+
+    // takes its time before it looks at the stop flag
+    struct Slow;
+
+    impl Engine for Slow {
+        fn search(&mut self, game: &Game, _: &AtomicBool) -> Result<Option<SearchResult>, String> {
+            thread::sleep(Duration::from_millis(300));
+            Ok(game.board().legal_moves().first().map(|best| SearchResult {
+                best: best.clone(),
+                score: 0,
+                depth: 1,
+                nodes: 1,
+            }))
+        }
+    }
+
+    fn wait(worker: &mut Worker) -> Result<Option<SearchResult>, String> {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while worker.is_thinking() && Instant::now() < deadline {
+            match worker.poll() {
+                Ok(None) => thread::sleep(Duration::from_millis(1)),
+                polled => return polled,
+            }
+        }
+        Ok(None)
+    }
+
+    #[test]
+    fn a_failing_engine_is_reported() {
+        let mut worker = Worker::new(Broken);
+        worker.start(&Game::default()).unwrap();
+        assert_eq!(wait(&mut worker).map(|_| ()), Err(String::from("broken")));
+        assert!(!worker.is_thinking());
+    }
+
+    #[test]
+    fn a_stopped_search_still_has_a_move() {
+        let mut worker = Worker::new(Searcher::new(64));
+        let game = Game::default();
+        worker.start(&game).unwrap();
+        worker.stop();
+        let result = wait(&mut worker).unwrap().unwrap();
+        assert!(game.board().legal_moves().contains(&result.best));
+    }
+
+    #[test]
+    fn a_lost_engine_is_reported() {
+        let mut worker = Worker::new(Panicking);
+        worker.start(&Game::default()).unwrap();
+        assert!(wait(&mut worker).is_err());
+        assert!(worker.start(&Game::default()).is_err());
+        assert!(!worker.is_thinking());
+    }
+
+    #[test]
+    fn a_cancelled_search_is_not_waited_for() {
+        let mut worker = Worker::new(Slow);
+        let game = Game::default();
+        worker.start(&game).unwrap();
+        let cancelled = Instant::now();
+        worker.cancel();
+        assert!(cancelled.elapsed() < Duration::from_millis(100));
+
+        // the next search waits for the engine to come back
+        worker.start(&game).unwrap();
+        assert!(worker.is_thinking());
+        let result = wait(&mut worker).unwrap().unwrap();
+        assert!(game.board().legal_moves().contains(&result.best));
+    }
+}
@@ -1,17 +1,29 @@
-pub struct WinColorEvent {
-    pub color: i8,
+use checkers::rules::{Game, Outcome};
+
+pub struct GameOverEvent {
+    pub outcome: Outcome,
+    /// How many hints the players asked for during the game.
+    pub hints: u32,
+    pub game: Game,
 }
 
-impl WinColorEvent {
-    pub fn is_green(&self) -> bool {
-        return self.color == 1;
+impl GameOverEvent {
+    pub fn new(outcome: Outcome, hints: u32, game: Game) -> GameOverEvent {
+        GameOverEvent {
+            outcome,
+            hints,
+            game,
+        }
     }
+}
 
-    pub fn new_green() -> WinColorEvent {
-        WinColorEvent { color: 1 }
-    }
+/// Asks for the game to be analysed and shown move by move.
+pub struct ReviewEvent {
+    pub game: Game,
+}
 
-    pub fn new_red() -> WinColorEvent {
-        WinColorEvent { color: 0 }
+impl ReviewEvent {
+    pub fn new(game: Game) -> ReviewEvent {
+        ReviewEvent { game }
     }
 }
@@ -34,14 +34,11 @@ impl<'state> Runtime<'state> {
     fn current_state_mut(&mut self) -> Result<&mut dyn GameStateTrait, String> {
         let current_index = self.current_index;
 
-        return if let Some(state) = self.states.get_mut(current_index) {
+        if let Some(state) = self.states.get_mut(current_index) {
             Ok(*state)
         } else {
-            Err(String::from(format!(
-                "No such state at index {}",
-                current_index
-            )))
-        };
+            Err(format!("No such state at index {}", current_index))
+        }
     }
 
     fn handle_update(&mut self, clock: &mut Clock) -> Result<RuntimeSignal, String> {
@@ -4,27 +4,104 @@ use crate::game_machine::runtime_signal::RuntimeSignal;
 use crate::game_machine::state::GameStateTrait;
 
 use crate::asset_loader::{Assets, TextureManager};
-use crate::game_events::WinColorEvent;
+use crate::game_events::GameOverEvent;
+use crate::game_states::chat::{ChatInput, ChatOverlay};
+use crate::game_states::Player;
+use checkers::engine::{Book, Worker};
+use checkers::net::{Clocks, Link, NetEvent};
+use checkers::notation::{parse_fen, write_fen, PdnGame};
+use checkers::rules::{Game, Move, MoveError, Position, Selection, Side, BOARD_LENGTH, BOARD_SIZE};
+use sdl2::clipboard::ClipboardUtil;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod, TextInputUtil};
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect;
 use sdl2::rect::Point;
 use sdl2::render::{Canvas, TextureCreator, TextureQuery};
 use sdl2::video::{Window, WindowContext};
-use std::borrow::BorrowMut;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const BOARD_LENGTH: usize = 8;
-const BOARD_SIZE: usize = BOARD_LENGTH * BOARD_LENGTH;
 const CONTAINER_WIDTH: usize = 100;
 const CHECKER_PADDING: usize = 20;
+const CHECKER_WIDTH: usize = CONTAINER_WIDTH - CHECKER_PADDING * 2;
 const OUTER_PADDING: usize = 20; // padding from the left most top corner of the screen
+const HINT_DURATION: Duration = Duration::from_secs(3);
+
+// texture keys after the per tile debug text
+const MESSAGE_TEXT: usize = BOARD_SIZE;
+const DRAW_OFFER_TEXT: usize = MESSAGE_TEXT + MoveError::ALL.len();
+const SAVED_TEXT: usize = DRAW_OFFER_TEXT + 1;
+const SAVE_FAILED_TEXT: usize = SAVED_TEXT + 1;
+const COPIED_TEXT: usize = SAVE_FAILED_TEXT + 1;
+const COPY_FAILED_TEXT: usize = COPIED_TEXT + 1;
+const PASTE_FAILED_TEXT: usize = COPY_FAILED_TEXT + 1;
+const THINKING_TEXT: usize = PASTE_FAILED_TEXT + 1;
+const BOOK_TEXT: usize = THINKING_TEXT + 1;
+const WAITING_TEXT: usize = BOOK_TEXT + 1;
+const JOINED_TEXT: usize = WAITING_TEXT + 1;
+const OPPONENT_TURN_TEXT: usize = JOINED_TEXT + 1;
+const REFUSED_TEXT: usize = OPPONENT_TURN_TEXT + 1;
+const LEFT_TEXT: usize = REFUSED_TEXT + 1;
+const LOST_TEXT: usize = LEFT_TEXT + 1;
+const RESUMED_TEXT: usize = LOST_TEXT + 1;
+const NET_FAILED_TEXT: usize = RESUMED_TEXT + 1;
+const ENGINE_FAILED_TEXT: usize = NET_FAILED_TEXT + 1;
+const HINT_FAILED_TEXT: usize = ENGINE_FAILED_TEXT + 1;
+// the glyphs the clocks of a network game are written with
+const CLOCK_GLYPHS: usize = HINT_FAILED_TEXT + 1;
+
+const NOTICES: [(usize, &str); 18] = [
+    (
+        DRAW_OFFER_TEXT,
+        "Draw offered. Press D to accept or move to decline",
+    ),
+    (SAVED_TEXT, "Game saved"),
+    (SAVE_FAILED_TEXT, "The game could not be saved"),
+    (COPIED_TEXT, "Position copied to the clipboard"),
+    (COPY_FAILED_TEXT, "The position could not be copied"),
+    (
+        PASTE_FAILED_TEXT,
+        "The clipboard does not hold a FEN position",
+    ),
+    (THINKING_TEXT, "Thinking..."),
+    (BOOK_TEXT, "Book move"),
+    (WAITING_TEXT, "Waiting for the opponent to join"),
+    (JOINED_TEXT, "The opponent has joined"),
+    (OPPONENT_TURN_TEXT, "Waiting for the opponent's move"),
+    (REFUSED_TEXT, "The host refused the move"),
+    (LEFT_TEXT, "The opponent has left the game"),
+    (LOST_TEXT, "Connection lost, waiting for reconnection"),
+    (RESUMED_TEXT, "The connection is back, the game goes on"),
+    (
+        NET_FAILED_TEXT,
+        "The opponent's end broke the game, the connection was closed",
+    ),
+    (
+        ENGINE_FAILED_TEXT,
+        "The computer failed, its side is played by hand now",
+    ),
+    (HINT_FAILED_TEXT, "The engine failed, no more hints"),
+];
+
+// minutes and seconds used by each side, as in "Green 3:07  Red 12:45"
+fn clocks_text(clocks: &Clocks) -> String {
+    let time = |side| {
+        let seconds = clocks.used(side).as_secs();
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    };
+    format!("Green {}  Red {}", time(Side::Green), time(Side::Red))
+}
+
+fn error_text(err: MoveError) -> usize {
+    MESSAGE_TEXT + MoveError::ALL.iter().position(|e| *e == err).unwrap_or(0)
+}
 
 trait RectExtras {
     fn clear(&mut self);
     fn move_to(&mut self, rect: &rect::Rect);
-    fn move_yellow(&mut self, rect: &rect::Rect);
 }
 
 impl RectExtras for rect::Rect {
@@ -39,88 +116,22 @@ impl RectExtras for rect::Rect {
         self.set_x(rect.x() + CHECKER_PADDING as i32);
         self.set_y(rect.y() + CHECKER_PADDING as i32);
     }
-
-    fn move_yellow(&mut self, rect: &rect::Rect) {
-        self.set_x(rect.x() + CHECKER_PADDING as i32);
-        self.set_y(rect.y() + CHECKER_PADDING as i32);
-    }
-}
-
-struct Score {
-    green: usize,
-    red: usize,
-}
-
-impl Score {
-    fn new() -> Score {
-        Score { green: 0, red: 0 }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Checker {
-    Green(usize), // sdl2 rect index
-    Red(usize),
-    SuperGreen(usize),
-    SuperRed(usize),
-    None,
-}
-
-impl Checker {
-    fn is_super(&self) -> bool {
-        match self {
-            Checker::SuperRed(..) | Checker::SuperGreen(..) => true,
-            _ => false,
-        }
-    }
-
-    fn is_green(&self) -> bool {
-        match self {
-            Checker::SuperGreen(..) | Checker::Green(..) => true,
-            _ => false,
-        }
-    }
-
-    fn is_red(&self) -> bool {
-        match self {
-            Checker::SuperRed(..) | Checker::Red(..) => true,
-            _ => false,
-        }
-    }
-
-    fn is_none(&self) -> bool {
-        match self {
-            Checker::None => true,
-            _ => false,
-        }
-    }
-
-    fn is_some(&self) -> bool {
-        !self.is_none()
-    }
-
-    fn is_same_color(&self, other: &Checker) -> bool {
-        (self.is_red() && other.is_red())
-            || (self.is_green() && other.is_green())
-            || (self.is_none() && other.is_none())
-    }
-
-    fn is_different_color(&self, other: &Checker) -> bool {
-        !self.is_same_color(other)
-    }
 }
 
 struct RenderRectangles {
     board_tiles: [rect::Rect; BOARD_SIZE],
     black_tiles: [rect::Rect; BOARD_SIZE / 2],
 
-    green_rectangles: [rect::Rect; BOARD_SIZE / 4],
-    red_rectangles: [rect::Rect; BOARD_SIZE / 4],
+    green_rectangles: [rect::Rect; BOARD_SIZE / 2],
+    red_rectangles: [rect::Rect; BOARD_SIZE / 2],
 
-    yellow_rectangles: [rect::Rect; BOARD_SIZE],
+    yellow_rectangles: [rect::Rect; BOARD_SIZE / 2],
 
     indicator: rect::Rect,
     debug_tile_text: [rect::Rect; BOARD_SIZE],
+    message: rect::Rect,
+    // the whole board, the chat is drawn over its lower part
+    board: rect::Rect,
 }
 
 impl RenderRectangles {
@@ -128,11 +139,13 @@ impl RenderRectangles {
         RenderRectangles {
             board_tiles: [rect::Rect::new(0, 0, 100, 100); BOARD_SIZE],
             black_tiles: [rect::Rect::new(0, 0, 100, 100); BOARD_SIZE / 2],
-            green_rectangles: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE / 4],
-            red_rectangles: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE / 4],
+            green_rectangles: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE / 2],
+            red_rectangles: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE / 2],
             indicator: rect::Rect::new(0, 0, 0, 0),
             debug_tile_text: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE],
-            yellow_rectangles: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE],
+            yellow_rectangles: [rect::Rect::new(0, 0, 0, 0); BOARD_SIZE / 2],
+            message: rect::Rect::new(0, 0, 0, 0),
+            board: rect::Rect::new(0, 0, 0, 0),
         }
     }
 }
@@ -140,341 +153,397 @@ impl RenderRectangles {
 pub struct BoardState<'ttf> {
     is_set_up: bool,
     renderings: RenderRectangles,
-    cell_mapping: [Checker; BOARD_SIZE],
-    green_length: usize,
-    red_length: usize,
-    score: Score,
+    game: Game,
     mouse_point: Point,
-    source_index: Option<usize>,
-    target_index: Option<usize>,
-    playing_color: Checker,
+    source_pos: Option<Position>,
+    target_pos: Option<Position>,
+    landing_path: Vec<Position>,
+    message: Option<usize>,
+    green_player: Player,
+    red_player: Player,
+    clipboard: ClipboardUtil,
+    book: Option<Arc<Book>>,
+    show_book: bool,
+    hinter: Option<Worker>,
+    hint: Option<(Move, Instant)>,
+    hints: u32,
+    link: Option<Box<dyn Link>>,
+    // the id a guest gets back into the game with
+    session: Option<u64>,
+    // a move sent to the host and not yet played
+    is_sending: bool,
+    chat: ChatOverlay<'ttf>,
     texture_manager: TextureManager<'ttf>,
 }
 
-fn is_in_bounds(pos: i32) -> bool {
-    pos >= 0 && pos < BOARD_SIZE as i32
-}
-
-fn on_left_edge(pos: usize) -> bool {
-    (pos as i32) % BOARD_LENGTH as i32 == 0
-}
-
-fn on_right_edge(pos: usize) -> bool {
-    ((pos as i32) + 1) % BOARD_LENGTH as i32 == 0
-}
-
-fn row_up(pos: usize, n: i32) -> i32 {
-    (pos as i32) - (n * BOARD_LENGTH as i32)
-}
-
-fn row_down(pos: usize, n: i32) -> i32 {
-    (pos as i32) + (n * BOARD_LENGTH as i32)
-}
-
-fn yellow_for_red(i: usize) -> usize {
-    BOARD_LENGTH + i
-}
-
-fn yellow_for_green(i: usize) -> usize {
-    i
-}
-
-fn on_last_line(i: usize) -> bool {
-    (BOARD_SIZE - BOARD_LENGTH) <= i && i < BOARD_SIZE
-}
-
-fn on_first_line(i: usize) -> bool {
-    i <= BOARD_LENGTH
-}
-
 impl<'ttf> BoardState<'ttf> {
-    pub fn new(t_creator: &'ttf TextureCreator<WindowContext>) -> BoardState<'ttf> {
+    pub fn new(
+        t_creator: &'ttf TextureCreator<WindowContext>,
+        game: Game,
+        clipboard: ClipboardUtil,
+    ) -> BoardState<'ttf> {
         BoardState {
             is_set_up: false,
             renderings: RenderRectangles::new(),
-            green_length: 0,
-            red_length: 0,
-            score: Score::new(),
+            game,
             mouse_point: Point::new(0, 0),
-            source_index: None,
-            target_index: None,
-            cell_mapping: [Checker::None; BOARD_SIZE],
-            playing_color: Checker::Green(0),
+            source_pos: None,
+            target_pos: None,
+            landing_path: vec![],
+            message: None,
+            green_player: Player::Human,
+            red_player: Player::Human,
+            clipboard,
+            book: None,
+            show_book: false,
+            hinter: None,
+            hint: None,
+            hints: 0,
+            link: None,
+            session: None,
+            is_sending: false,
+            chat: ChatOverlay::new(t_creator),
             texture_manager: TextureManager::new(t_creator),
         }
     }
 
-    fn get_source_checker_ref(&self) -> Option<(&Checker, usize)> {
-        if let Some(i) = self.source_index {
-            Some((&self.cell_mapping[i], i))
-        } else {
-            None
-        }
+    /// The book is only used to tell when the game is still in it, the B key toggles showing that.
+    pub fn with_book(mut self, book: Arc<Book>) -> BoardState<'ttf> {
+        self.book = Some(book);
+        self
     }
 
-    fn switch_turn(&mut self) {
-        match self.playing_color {
-            Checker::Red(sdl_rect) => self.playing_color = Checker::Green(sdl_rect),
-            Checker::Green(sdl_rect) => self.playing_color = Checker::Red(sdl_rect),
-            _ => {}
-        }
+    /// The engine asked for the best move when the H key is pressed.
+    pub fn with_hinter(mut self, hinter: Worker) -> BoardState<'ttf> {
+        self.hinter = Some(hinter);
+        self
     }
 
-    fn find_source_checker_rect(&self) -> Option<usize> {
-        for i in 0..self.renderings.board_tiles.len() {
-            let rect = &self.renderings.board_tiles[i];
-            if rect.contains_point(self.mouse_point) {
-                let cell = &self.cell_mapping[i];
-                if cell.is_same_color(&self.playing_color) {
-                    return Some(i);
-                }
-            }
+    /// Plays a network game: the other side is played on the far end of `link`.
+    pub fn with_link(mut self, link: Box<dyn Link>) -> BoardState<'ttf> {
+        let remote = link.side().opponent();
+        self.link = Some(link);
+        self.with_player(remote, Player::Remote)
+    }
+
+    /// Shows the session a guest has joined, for resuming it after a restart, while there is
+    /// nothing else to tell.
+    pub fn with_session(mut self, session: u64) -> BoardState<'ttf> {
+        self.session = Some(session);
+        self
+    }
+
+    /// Lets the player chat with the opponent, Enter starts a line.
+    pub fn with_chat(mut self, text_input: TextInputUtil) -> BoardState<'ttf> {
+        self.chat = self.chat.with_text_input(text_input);
+        self
+    }
+
+    pub fn with_player(mut self, side: Side, player: Player) -> BoardState<'ttf> {
+        match side {
+            Side::Green => self.green_player = player,
+            Side::Red => self.red_player = player,
         }
-        None
+        self
     }
 
-    fn find_target_rect(&mut self) -> Option<usize> {
-        for i in 0..self.renderings.board_tiles.len() {
-            let rect = &mut self.renderings.board_tiles[i];
-            if rect.contains_point(self.mouse_point) {
-                return Some(i);
-            }
+    fn player_to_move(&self) -> &Player {
+        match self.game.board().turn() {
+            Side::Green => &self.green_player,
+            Side::Red => &self.red_player,
         }
-        None
     }
 
-    fn move_to_empty(&mut self, source: usize, target: usize) {
-        match self.cell_mapping[source] {
-            Checker::Red(sdl_rect) => {
-                let rct = &mut self.renderings.red_rectangles[sdl_rect];
-                rct.move_to(&self.renderings.board_tiles[target]);
+    // clicks only count while a local player is to move and nothing is on its way to the host
+    fn accepts_input(&self) -> bool {
+        let is_ready = match &self.link {
+            Some(link) => link.is_connected() && !self.is_sending,
+            None => true,
+        };
+        is_ready && self.player_to_move().is_human()
+    }
 
-                if on_first_line(target) {
-                    // first line
-                    {
-                        self.cell_mapping[target] = Checker::SuperRed(sdl_rect);
+    fn poll_link(&mut self) {
+        let mut events = vec![];
+        if let Some(link) = &mut self.link {
+            loop {
+                match link.poll() {
+                    Ok(Some(event)) => events.push(event),
+                    Ok(None) => break,
+                    Err(e) => {
+                        events.push(NetEvent::Failed(e));
+                        break;
                     }
-                    if let Some(a) = self
-                        .renderings
-                        .yellow_rectangles
-                        .get_mut(yellow_for_red(sdl_rect))
-                    {
-                        a.set_x(rct.x());
-                        a.set_y(rct.y());
-                        a.set_width(20);
-                        a.set_height(20);
-                    }
-                } else {
-                    self.cell_mapping[target] = Checker::Red(sdl_rect);
                 }
             }
-            Checker::Green(sdl_rect) => {
-                let rct = &mut self.renderings.green_rectangles[sdl_rect];
-                rct.move_to(&self.renderings.board_tiles[target]);
-
-                if on_last_line(target) {
-                    // last line
-                    {
-                        self.cell_mapping[target] = Checker::SuperGreen(sdl_rect);
-                    }
+        }
 
-                    if let Some(a) = self
-                        .renderings
-                        .yellow_rectangles
-                        .get_mut(yellow_for_green(sdl_rect))
-                    {
-                        a.set_x(rct.x());
-                        a.set_y(rct.y());
-                        a.set_width(20);
-                        a.set_height(20);
-                    }
-                } else {
-                    self.cell_mapping[target] = Checker::Green(sdl_rect);
+        for event in events {
+            match event {
+                NetEvent::Joined(_) => self.message = Some(JOINED_TEXT),
+                NetEvent::Moved(mv) => {
+                    self.is_sending = false;
+                    self.clear_hint();
+                    self.game.play(&mv);
+                    self.place_checkers();
                 }
-            }
-            Checker::SuperGreen(sdl_rect) => {
-                let target_rct = &self.renderings.board_tiles[target];
-                {
-                    let rct = &mut self.renderings.green_rectangles[sdl_rect];
-                    rct.move_to(target_rct);
+                NetEvent::Refused(_) => {
+                    self.is_sending = false;
+                    self.message = Some(REFUSED_TEXT);
                 }
-
-                {
-                    let rct = &mut self.renderings.yellow_rectangles[yellow_for_green(sdl_rect)];
-                    rct.move_yellow(target_rct);
+                NetEvent::Lost => self.message = Some(LOST_TEXT),
+                NetEvent::Resumed => {
+                    // a move sent before the connection dropped is in the game by now, or lost
+                    self.is_sending = false;
+                    self.message = Some(RESUMED_TEXT);
                 }
-
-                self.cell_mapping[target] = Checker::SuperGreen(sdl_rect);
-            }
-            Checker::SuperRed(sdl_rect) => {
-                let target_rct = &self.renderings.board_tiles[target];
-                {
-                    let rct = &mut self.renderings.red_rectangles[sdl_rect];
-                    rct.move_to(target_rct);
+                NetEvent::Left => {
+                    // a guest gone for good gives up the game, rather than leaving it hanging
+                    if let Some(link) = self.link.as_ref().filter(|link| link.is_host()) {
+                        self.game.forfeit(link.side().opponent());
+                    }
+                    self.message = Some(LEFT_TEXT);
                 }
-
-                {
-                    let rct = &mut self.renderings.yellow_rectangles[yellow_for_red(sdl_rect)];
-                    rct.move_yellow(target_rct);
+                // nothing more is heard from that end, so the game ends like when it left
+                NetEvent::Failed(_) => {
+                    if let Some(link) = &self.link {
+                        self.game.forfeit(link.side().opponent());
+                    }
+                    self.message = Some(NET_FAILED_TEXT);
                 }
-
-                self.cell_mapping[target] = Checker::SuperRed(sdl_rect);
+                NetEvent::Chat { name, text } => self.chat.push(&name, &text),
             }
-            _ => {}
         }
-        self.cell_mapping[source] = Checker::None;
     }
 
-    fn overtake(&mut self, source: usize, victim: usize, end: usize) {
-        match self.cell_mapping[victim] {
-            Checker::Green(sdl_rect) => {
-                self.renderings.green_rectangles[sdl_rect]
-                    .borrow_mut()
-                    .clear();
-                self.score.green -= 1;
+    // the local player's move, which in a network game is only played once the host agrees
+    fn play_human_move(&mut self, mv: &Move) {
+        self.clear_hint();
+        self.message = None;
+        match &mut self.link {
+            Some(link) => match link.send_move(mv) {
+                Ok(()) => self.is_sending = true,
+                Err(_) => self.message = Some(REFUSED_TEXT),
+            },
+            None => {
+                self.game.play(mv);
+                self.place_checkers();
             }
-            Checker::Red(sdl_rect) => {
-                self.renderings.red_rectangles[sdl_rect]
-                    .borrow_mut()
-                    .clear();
-                self.score.red -= 1;
-            }
-            Checker::SuperGreen(sdl_rect) => {
-                {
-                    self.renderings.green_rectangles[sdl_rect]
-                        .borrow_mut()
-                        .clear();
+        }
+    }
+
+    // the search runs on a worker thread, so this only starts it or picks up its move
+    fn play_computer_move(&mut self) {
+        let player = match self.game.board().turn() {
+            Side::Green => &mut self.green_player,
+            Side::Red => &mut self.red_player,
+        };
+
+        if let Player::Computer(worker) = player {
+            let polled = if worker.is_thinking() {
+                worker.poll()
+            } else {
+                worker.start(&self.game).map(|_| None)
+            };
+            match polled {
+                Ok(Some(result)) => {
+                    self.game.play(&result.best);
+                    self.place_checkers();
                 }
-                self.renderings.yellow_rectangles[yellow_for_green(sdl_rect)]
-                    .borrow_mut()
-                    .clear();
-                self.score.green -= 1;
-            }
-            Checker::SuperRed(sdl_rect) => {
-                {
-                    self.renderings.red_rectangles[sdl_rect]
-                        .borrow_mut()
-                        .clear();
+                Ok(None) => {}
+                // searching again would only fail again
+                Err(_) => {
+                    *player = Player::Human;
+                    self.message = Some(ENGINE_FAILED_TEXT);
                 }
-                self.renderings.yellow_rectangles[yellow_for_red(sdl_rect)]
-                    .borrow_mut()
-                    .clear();
-                self.score.red -= 1;
             }
-            _ => {}
-        };
-        self.cell_mapping[victim] = Checker::None;
-        self.move_to_empty(source, end);
+        }
     }
 
-    fn try_to_overtake(&mut self, source: usize, victim: usize, end: usize) {
-        let is_enemy = self.cell_mapping[source].is_different_color(&self.cell_mapping[victim]);
-        let is_next_empty = self.cell_mapping[end].is_none();
+    fn cancel_thinking(&mut self) {
+        self.green_player.cancel_thinking();
+        self.red_player.cancel_thinking();
+        self.clear_hint();
+    }
 
-        if is_enemy && is_next_empty {
-            self.overtake(source, victim, end);
+    // hints are only given to a human to move, and counted when asked for
+    fn ask_hint(&mut self) {
+        if self.game.is_over() || !self.player_to_move().is_human() || self.hint.is_some() {
+            return;
+        }
+        if let Some(hinter) = &mut self.hinter {
+            if !hinter.is_thinking() {
+                match hinter.start(&self.game) {
+                    Ok(()) => self.hints += 1,
+                    Err(_) => {
+                        self.hinter = None;
+                        self.message = Some(HINT_FAILED_TEXT);
+                    }
+                }
+            }
         }
     }
 
-    fn check_next(&mut self, source_pos: usize, target_pos: usize, is_up: bool, is_left: bool) {
-        let next_diagonal = if is_up {
-            if is_left {
-                row_up(target_pos, 1) - 1
-            } else {
-                row_up(target_pos, 1) + 1
+    fn poll_hint(&mut self) {
+        let polled = match &mut self.hinter {
+            Some(hinter) => hinter.poll(),
+            None => Ok(None),
+        };
+        match polled {
+            Ok(Some(result)) => self.hint = Some((result.best, Instant::now())),
+            Ok(None) => {}
+            Err(_) => {
+                self.hinter = None;
+                self.message = Some(HINT_FAILED_TEXT);
             }
-        } else {
-            if is_left {
-                row_down(target_pos, 1) - 1
-            } else {
-                row_down(target_pos, 1) + 1
+        }
+        if let Some((_, shown)) = self.hint {
+            if shown.elapsed() >= HINT_DURATION {
+                self.hint = None;
             }
-        };
-        if !(on_right_edge(target_pos) || on_left_edge(target_pos)) && is_in_bounds(next_diagonal) {
-            self.try_to_overtake(source_pos, target_pos, next_diagonal as usize);
         }
     }
 
-    fn scan_neighbourhood(&mut self, source_pos: usize, target_pos: usize) {
-        if !on_right_edge(source_pos) {
-            // east-west boundary check
-            self.iterator_diagonal(source_pos, target_pos, 1, false, false);
-            self.iterator_diagonal(source_pos, target_pos, 1, true, false);
-        }
+    fn is_hinting(&self) -> bool {
+        self.hinter.as_ref().is_some_and(Worker::is_thinking)
+    }
 
-        if !on_left_edge(source_pos) {
-            // east-west boundary check
-            self.iterator_diagonal(source_pos, target_pos, 1, false, true);
-            self.iterator_diagonal(source_pos, target_pos, 1, true, true);
+    // a hint only holds for the position it was asked in
+    fn clear_hint(&mut self) {
+        if let Some(hinter) = &mut self.hinter {
+            hinter.cancel();
         }
+        self.hint = None;
     }
 
-    fn iterator_diagonal(
-        &mut self,
-        source_pos: usize,
-        target_pos: usize,
-        j: i32,
-        is_up: bool,
-        is_left: bool,
-    ) -> bool {
-        let next_diagonal = if is_up {
-            if is_left {
-                row_up(source_pos, j) - j
-            } else {
-                row_up(source_pos, j) + j
-            }
-        } else {
-            if is_left {
-                row_down(source_pos, j) - j
-            } else {
-                row_down(source_pos, j) + j
+    // steps back until a human is to move, so the computer does not replay its move at once
+    fn undo_move(&mut self) {
+        // a network game only changes through the host
+        if self.link.is_some() {
+            return;
+        }
+        self.cancel_thinking();
+        while self.game.undo() {
+            if self.player_to_move().is_human() {
+                break;
             }
-        };
-
-        let is_target = next_diagonal as usize == target_pos;
+        }
+        self.reset_selection();
+    }
 
-        if is_in_bounds(next_diagonal) {
-            if self.cell_mapping[target_pos].is_none() && is_target {
-                self.move_to_empty(source_pos, target_pos);
-                self.switch_turn();
-                return false;
-            } else if self.cell_mapping[next_diagonal as usize].is_some() {
-                if is_target {
-                    self.check_next(source_pos, target_pos, is_up, is_left);
-                }
-                return false;
+    fn redo_move(&mut self) {
+        if self.link.is_some() {
+            return;
+        }
+        self.cancel_thinking();
+        while self.game.redo() {
+            if self.player_to_move().is_human() {
+                break;
             }
-        } else {
-            return false;
         }
-        true
+        self.reset_selection();
     }
 
-    fn scan_diagonals(&mut self, source_pos: usize, target_pos: usize) {
-        if !on_right_edge(source_pos) {
-            for i in 1..=BOARD_LENGTH {
-                if !self.iterator_diagonal(source_pos, target_pos, i as i32, false, false) {
-                    break;
-                }
-            }
-            for i in 1..=BOARD_LENGTH {
-                if !self.iterator_diagonal(source_pos, target_pos, i as i32, true, false) {
-                    break;
-                }
-            }
+    fn is_book_position(&self) -> bool {
+        match &self.book {
+            Some(book) => self.show_book && book.contains(self.game.board()),
+            None => false,
         }
+    }
 
-        if !on_left_edge(source_pos) {
-            for i in 1..=BOARD_LENGTH {
-                if !self.iterator_diagonal(source_pos, target_pos, i as i32, false, true) {
-                    break;
-                }
-            }
+    fn find_clicked_tile(&self) -> Option<Position> {
+        self.renderings
+            .board_tiles
+            .iter()
+            .position(|rect| rect.contains_point(self.mouse_point))
+            .and_then(Position::from_index)
+    }
+
+    fn find_source_checker(&self) -> Option<Position> {
+        self.find_clicked_tile()
+            .filter(|pos| match self.game.board().piece_at(*pos) {
+                Some(piece) => piece.side == self.game.board().turn(),
+                None => false,
+            })
+    }
+
+    fn save_game(&self) -> Result<PathBuf, String> {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let path = PathBuf::from(format!("checkers-{}.pdn", seconds));
+
+        std::fs::write(&path, PdnGame::from_game(&self.game).to_string())
+            .map_err(|e| e.to_string())?;
+        Ok(path)
+    }
 
-            for i in 1..=BOARD_LENGTH {
-                if !self.iterator_diagonal(source_pos, target_pos, i as i32, true, true) {
-                    break;
+    fn copy_position(&self) -> Result<(), String> {
+        self.clipboard
+            .set_clipboard_text(&write_fen(self.game.board()))
+    }
+
+    // a pasted position starts a new game from there
+    fn paste_position(&mut self) -> Result<(), String> {
+        // the position comes without rules, the game's are kept
+        let board =
+            parse_fen(&self.clipboard.clipboard_text()?)?.with_rules(self.game.board().rules());
+        self.cancel_thinking();
+        self.game = Game::new(board);
+        self.hints = 0;
+        Ok(())
+    }
+
+    // after the board changed underneath a half made move
+    fn reset_selection(&mut self) {
+        self.source_pos = None;
+        self.target_pos = None;
+        self.landing_path.clear();
+        self.message = None;
+        self.place_checkers();
+    }
+
+    // the checker rectangles are a view of the board, so they are rebuilt after every move
+    fn place_checkers(&mut self) {
+        let renderings = &mut self.renderings;
+
+        for rct in renderings
+            .green_rectangles
+            .iter_mut()
+            .chain(renderings.red_rectangles.iter_mut())
+            .chain(renderings.yellow_rectangles.iter_mut())
+        {
+            rct.clear();
+        }
+
+        let mut green_length = 0;
+        let mut red_length = 0;
+        let mut yellow_length = 0;
+
+        for (pos, piece) in self.game.board().pieces() {
+            let rct = match piece.side {
+                Side::Green => {
+                    green_length += 1;
+                    &mut renderings.green_rectangles[green_length - 1]
+                }
+                Side::Red => {
+                    red_length += 1;
+                    &mut renderings.red_rectangles[red_length - 1]
                 }
+            };
+            rct.set_width(CHECKER_WIDTH as u32);
+            rct.set_height(CHECKER_WIDTH as u32);
+            rct.move_to(&renderings.board_tiles[pos.index()]);
+
+            if piece.is_king() {
+                let (x, y) = (rct.x(), rct.y());
+                let crown = &mut renderings.yellow_rectangles[yellow_length];
+                crown.set_x(x);
+                crown.set_y(y);
+                crown.set_width(20);
+                crown.set_height(20);
+                yellow_length += 1;
             }
         }
     }
@@ -482,27 +551,30 @@ impl<'ttf> BoardState<'ttf> {
 
 impl GameStateTrait for BoardState<'_> {
     fn update(&mut self, event: &sdl2::EventSubsystem) -> Result<RuntimeSignal, String> {
-        if self.score.red <= 0 || self.score.green <= 0 {
-            if self.score.red <= 0 {
-                event.push_custom_event(WinColorEvent::new_green()).unwrap();
-            } else {
-                event.push_custom_event(WinColorEvent::new_red()).unwrap();
-            }
+        self.poll_link();
+
+        if let Some(outcome) = self.game.outcome() {
+            event.push_custom_event(GameOverEvent::new(outcome, self.hints, self.game.clone()))?;
 
             Ok(RuntimeSignal::GotoState(1))
+        } else if !self.player_to_move().is_human() {
+            self.play_computer_move();
+            Ok(RuntimeSignal::Continue)
         } else {
-            if self.source_index.is_some() && self.target_index.is_some() {
-                let (source_checker, source_i) = self.get_source_checker_ref().unwrap();
-                let target_i = self.target_index.unwrap();
-
-                if source_checker.is_super() {
-                    self.scan_diagonals(source_i, target_i);
-                } else {
-                    self.scan_neighbourhood(source_i, target_i);
+            self.poll_hint();
+
+            if let (Some(source), Some(target)) = (self.source_pos, self.target_pos.take()) {
+                self.landing_path.push(target);
+
+                match self.game.board().find_move(source, &self.landing_path) {
+                    Ok(Selection::Complete(mv)) => self.play_human_move(&mv),
+                    // capture chain continues, wait for the next landing square
+                    Ok(Selection::Partial) => return Ok(RuntimeSignal::Continue),
+                    Err(err) => self.message = Some(error_text(err)),
                 }
 
-                self.source_index = None;
-                self.target_index = None;
+                self.source_pos = None;
+                self.landing_path.clear();
             }
             Ok(RuntimeSignal::Continue)
         }
@@ -528,9 +600,20 @@ impl GameStateTrait for BoardState<'_> {
             }
         }
 
-        if let Some(i) = self.source_index {
+        if let Some(pos) = self.source_pos {
             canvas.set_draw_color(Color::RGB(0x0, 0x0f, 0xfa));
-            canvas.fill_rect(self.renderings.board_tiles[i])?;
+            canvas.fill_rect(self.renderings.board_tiles[pos.index()])?;
+        }
+
+        canvas.set_draw_color(Color::RGB(0x6f, 0x8f, 0xfa));
+        for pos in &self.landing_path {
+            canvas.fill_rect(self.renderings.board_tiles[pos.index()])?;
+        }
+
+        if let Some((mv, _)) = &self.hint {
+            canvas.set_draw_color(Color::RGB(0xff, 0xa5, 0x0));
+            canvas.fill_rect(self.renderings.board_tiles[mv.from.index()])?;
+            canvas.fill_rect(self.renderings.board_tiles[mv.to().index()])?;
         }
 
         canvas.set_draw_color(Color::RGB(0x0, 0xff, 0x0));
@@ -542,42 +625,177 @@ impl GameStateTrait for BoardState<'_> {
         canvas.set_draw_color(Color::RGB(0xef, 0xef, 0x00));
         canvas.fill_rects(&self.renderings.yellow_rectangles)?;
 
-        if self.playing_color.is_green() {
-            canvas.set_draw_color(Color::RGB(0x0, 0xff, 0x0));
-        } else if self.playing_color.is_red() {
-            canvas.set_draw_color(Color::RGB(0xff, 0x0, 0x0));
-        } else {
-            canvas.set_draw_color(Color::RGB(0xea, 0xea, 0xea));
+        match self.game.board().turn() {
+            Side::Green => canvas.set_draw_color(Color::RGB(0x0, 0xff, 0x0)),
+            Side::Red => canvas.set_draw_color(Color::RGB(0xff, 0x0, 0x0)),
         }
 
         canvas.fill_rect(self.renderings.indicator)?;
 
+        let message = match (self.message, self.game.draw_offer()) {
+            (Some(key), _) => Some(key),
+            (None, Some(_)) => Some(DRAW_OFFER_TEXT),
+            (None, None) if self.player_to_move().is_thinking() || self.is_hinting() => {
+                Some(THINKING_TEXT)
+            }
+            (None, None) if self.is_book_position() => Some(BOOK_TEXT),
+            (None, None) => match &self.link {
+                Some(link) if !link.is_connected() => Some(WAITING_TEXT),
+                Some(link) if self.game.board().turn() != link.side() => Some(OPPONENT_TURN_TEXT),
+                _ => None,
+            },
+        };
+
+        match (message, self.session) {
+            (Some(key), _) => {
+                if let Some(twi) = self.texture_manager.get_texture(key) {
+                    let TextureQuery { width, height, .. } = twi.get_texture_info_ref();
+                    let mut dst = self.renderings.message;
+                    dst.set_width(*width);
+                    dst.set_height(*height);
+                    canvas.copy(twi.get_texture_ref(), None, dst)?;
+                }
+            }
+            (None, Some(session)) => self.texture_manager.draw_text(
+                canvas,
+                CLOCK_GLYPHS,
+                &format!("Session {:x}", session),
+                self.renderings.message.x(),
+                self.renderings.message.y(),
+            )?,
+            (None, None) => {}
+        }
+
+        if let Some(link) = &self.link {
+            let clocks = clocks_text(&link.clocks());
+            let width = self.texture_manager.text_width(CLOCK_GLYPHS, &clocks) as i32;
+            self.texture_manager.draw_text(
+                canvas,
+                CLOCK_GLYPHS,
+                &clocks,
+                self.renderings.board.right() - width,
+                self.renderings.message.y(),
+            )?;
+        }
+
+        self.chat.render(canvas, self.renderings.board)?;
+
         canvas.present();
         Ok(())
     }
 
     fn handle_event(&mut self, event: &Event) -> Result<RuntimeSignal, String> {
+        // while a line is typed, keys belong to the chat
+        if let Some(link) = &mut self.link {
+            match self.chat.handle_event(event) {
+                ChatInput::Ignored => {}
+                ChatInput::Handled => return Ok(RuntimeSignal::Continue),
+                ChatInput::Sent(text) => {
+                    if link.send_chat(&text).is_err() {
+                        self.message = Some(LEFT_TEXT);
+                    }
+                    return Ok(RuntimeSignal::Continue);
+                }
+            }
+        }
+
         match event {
-            Event::Quit { .. } => return Ok(RuntimeSignal::Quit),
+            Event::Quit { .. } => Ok(RuntimeSignal::Quit),
             Event::KeyDown {
                 keycode: Some(Keycode::Escape),
                 ..
             } => Ok(RuntimeSignal::GotoState(2)),
+            Event::KeyDown {
+                keycode: Some(Keycode::D),
+                ..
+            } => {
+                // draw offers are not part of the network protocol
+                if self.link.is_none() {
+                    self.game.offer_draw();
+                }
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::B),
+                ..
+            } => {
+                self.show_book = !self.show_book;
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::H),
+                ..
+            } => {
+                self.ask_hint();
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::S),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                self.message = match self.save_game() {
+                    Ok(_) => Some(SAVED_TEXT),
+                    Err(_) => Some(SAVE_FAILED_TEXT),
+                };
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::C),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                self.message = match self.copy_position() {
+                    Ok(()) => Some(COPIED_TEXT),
+                    Err(_) => Some(COPY_FAILED_TEXT),
+                };
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::V),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                // a network game only changes through the host
+                if self.link.is_none() {
+                    match self.paste_position() {
+                        Ok(()) => self.reset_selection(),
+                        Err(_) => self.message = Some(PASTE_FAILED_TEXT),
+                    }
+                }
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Z),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                self.undo_move();
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Y),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                self.redo_move();
+                Ok(RuntimeSignal::Continue)
+            }
             Event::MouseButtonDown {
                 x,
                 y,
                 mouse_btn: MouseButton::Left,
                 ..
-            } => {
+            } if self.accepts_input() => {
                 self.mouse_point.x = *x;
                 self.mouse_point.y = *y;
-                match self.source_index {
-                    None => self.source_index = self.find_source_checker_rect(),
-                    Some(_) => self.target_index = self.find_target_rect(),
+                match self.source_pos {
+                    None => self.source_pos = self.find_source_checker(),
+                    Some(_) => self.target_pos = self.find_clicked_tile(),
                 };
                 Ok(RuntimeSignal::Continue)
             }
-            _ => Ok(RuntimeSignal::Continue)
+            _ => Ok(RuntimeSignal::Continue),
         }
     }
 
@@ -610,26 +828,6 @@ impl GameStateTrait for BoardState<'_> {
                 black_tile.set_y(container.y());
 
                 tile_index += 1;
-            } else if y < (BOARD_LENGTH / 2 - 1) {
-                // green stuff
-                let checker_rect = &mut self.renderings.green_rectangles[self.green_length];
-                checker_rect.set_x((container.x() + CHECKER_PADDING as i32) as i32);
-                checker_rect.set_y((container.y() + CHECKER_PADDING as i32) as i32);
-                checker_rect.set_width((CONTAINER_WIDTH - CHECKER_PADDING * 2) as u32);
-                checker_rect.set_height((CONTAINER_WIDTH - CHECKER_PADDING * 2) as u32);
-
-                self.cell_mapping[flat_index] = Checker::Green(self.green_length);
-                self.green_length += 1;
-            } else if y > (BOARD_LENGTH / 2) {
-                // red stuff
-                let checker_rect = &mut self.renderings.red_rectangles[self.red_length];
-                checker_rect.set_x((container.x() + CHECKER_PADDING as i32) as i32);
-                checker_rect.set_y((container.y() + CHECKER_PADDING as i32) as i32);
-                checker_rect.set_width((CONTAINER_WIDTH - CHECKER_PADDING * 2) as u32);
-                checker_rect.set_height((CONTAINER_WIDTH - CHECKER_PADDING * 2) as u32);
-
-                self.cell_mapping[flat_index] = Checker::Red(self.red_length);
-                self.red_length += 1;
             }
 
             if let Some(font_with_info) = ass.font_collection.b612_regular.get(&12) {
@@ -653,8 +851,50 @@ impl GameStateTrait for BoardState<'_> {
             }
         }
 
-        self.score.red = self.red_length;
-        self.score.green = self.green_length;
+        {
+            // refused moves are explained just below the board
+            let message = &mut self.renderings.message;
+            message.set_x(OUTER_PADDING as i32);
+            message.set_y((CONTAINER_WIDTH * BOARD_LENGTH + OUTER_PADDING + 4) as i32);
+        }
+
+        if let Some(font_with_info) = ass.font_collection.b612_regular.get(&18) {
+            self.texture_manager.insert_glyphs(
+                CLOCK_GLYPHS,
+                font_with_info.font_ref(),
+                Color::RGB(0x0, 0x0, 0x0),
+            )?;
+
+            let font = font_with_info.font_ref();
+            for (i, err) in MoveError::ALL.iter().enumerate() {
+                self.texture_manager.insert_surface_as_texture(
+                    MESSAGE_TEXT + i,
+                    font.render(err.to_string().as_ref())
+                        .blended(Color::RGB(0xaf, 0x0, 0x0))
+                        .map_err(|e| e.to_string())?,
+                )?;
+            }
+
+            for (key, text) in NOTICES.iter() {
+                self.texture_manager.insert_surface_as_texture(
+                    *key,
+                    font.render(text)
+                        .blended(Color::RGB(0x0, 0x0, 0xaf))
+                        .map_err(|e| e.to_string())?,
+                )?;
+            }
+        }
+
+        {
+            let board = &mut self.renderings.board;
+            board.set_x(OUTER_PADDING as i32);
+            board.set_y(OUTER_PADDING as i32);
+            board.set_width((CONTAINER_WIDTH * BOARD_LENGTH) as u32);
+            board.set_height((CONTAINER_WIDTH * BOARD_LENGTH) as u32);
+        }
+        self.chat.setup(ass)?;
+
+        self.place_checkers();
 
         self.is_set_up = true;
         Ok(())
@@ -0,0 +1,238 @@
+use crate::asset_loader::{Assets, TextureManager};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, TextInputUtil};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use std::time::{Duration, Instant};
+
+const GLYPHS: usize = 0;
+// lines of the log shown at once
+const SHOWN_LINES: usize = 6;
+const MAX_LENGTH: usize = 200;
+// how long the log stays up after a line was said, when nobody is typing
+const SHOW_DURATION: Duration = Duration::from_secs(8);
+const PADDING: i32 = 8;
+
+/// What the chat made of an event.
+pub enum ChatInput {
+    /// Not meant for the chat, the board may have it.
+    Ignored,
+    Handled,
+    /// A line to send.
+    Sent(String),
+}
+
+/// The chat of a network game, drawn over the lower part of the board. Enter starts typing,
+/// Enter again sends and Escape gives up, Up and Down scroll through the log meanwhile.
+pub struct ChatOverlay<'ttf> {
+    texture_manager: TextureManager<'ttf>,
+    // none when only reading along
+    text_input: Option<TextInputUtil>,
+    log: Vec<String>,
+    input: Option<String>,
+    // lines scrolled back from the latest one
+    scroll: usize,
+    last_said: Option<Instant>,
+    line_height: i32,
+}
+
+impl<'ttf> ChatOverlay<'ttf> {
+    pub fn new(text_creator: &'ttf TextureCreator<WindowContext>) -> ChatOverlay<'ttf> {
+        ChatOverlay {
+            texture_manager: TextureManager::new(text_creator),
+            text_input: None,
+            log: vec![],
+            input: None,
+            scroll: 0,
+            last_said: None,
+            line_height: 0,
+        }
+    }
+
+    /// Lets lines be typed, not only read.
+    pub fn with_text_input(mut self, text_input: TextInputUtil) -> ChatOverlay<'ttf> {
+        self.text_input = Some(text_input);
+        self
+    }
+
+    pub fn setup(&mut self, ass: &Assets) -> Result<(), String> {
+        if let Some(font_with_info) = ass.font_collection.b612_regular.get(&18) {
+            self.line_height = font_with_info.font_size() as i32 + 6;
+            self.texture_manager.insert_glyphs(
+                GLYPHS,
+                font_with_info.font_ref(),
+                Color::RGB(0xff, 0xff, 0xff),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn push(&mut self, name: &str, text: &str) {
+        self.log.push(format!("{}: {}", name, text));
+        self.last_said = Some(Instant::now());
+        self.scroll = 0;
+    }
+
+    pub fn is_typing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> ChatInput {
+        let input = match &mut self.input {
+            Some(input) => input,
+            None => {
+                return match event {
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return),
+                        ..
+                    } if self.text_input.is_some() => {
+                        self.start_typing();
+                        ChatInput::Handled
+                    }
+                    _ => ChatInput::Ignored,
+                }
+            }
+        };
+
+        match event {
+            Event::TextInput { text, .. } => {
+                for c in text.chars() {
+                    if input.chars().count() < MAX_LENGTH {
+                        input.push(c);
+                    }
+                }
+                ChatInput::Handled
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Backspace),
+                ..
+            } => {
+                input.pop();
+                ChatInput::Handled
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Return),
+                ..
+            } => {
+                let text = input.trim().to_string();
+                self.stop_typing();
+                if text.is_empty() {
+                    ChatInput::Handled
+                } else {
+                    ChatInput::Sent(text)
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => {
+                self.stop_typing();
+                ChatInput::Handled
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Up),
+                ..
+            } => {
+                self.scroll = (self.scroll + 1).min(self.log.len().saturating_sub(1));
+                ChatInput::Handled
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Down),
+                ..
+            } => {
+                self.scroll = self.scroll.saturating_sub(1);
+                ChatInput::Handled
+            }
+            // typed letters are not the board's shortcuts
+            Event::KeyDown { .. } | Event::KeyUp { .. } => ChatInput::Handled,
+            _ => ChatInput::Ignored,
+        }
+    }
+
+    fn start_typing(&mut self) {
+        if let Some(text_input) = &self.text_input {
+            text_input.start();
+        }
+        self.input = Some(String::new());
+    }
+
+    fn stop_typing(&mut self) {
+        if let Some(text_input) = &self.text_input {
+            text_input.stop();
+        }
+        self.input = None;
+        self.scroll = 0;
+    }
+
+    fn is_shown(&self) -> bool {
+        self.is_typing()
+            || self
+                .last_said
+                .is_some_and(|said| said.elapsed() < SHOW_DURATION)
+    }
+
+    /// Draws the log along the bottom of `area`, while typing or shortly after a line was said.
+    pub fn render(&self, canvas: &mut Canvas<Window>, area: Rect) -> Result<(), String> {
+        if !self.is_shown() {
+            return Ok(());
+        }
+
+        let width = area.width() as i32 - PADDING * 2;
+        let mut lines: Vec<String> = self
+            .log
+            .iter()
+            .flat_map(|line| self.wrap(line, width))
+            .collect();
+        lines.truncate(lines.len() - self.scroll.min(lines.len()));
+        let first = lines.len().saturating_sub(SHOWN_LINES);
+        let mut shown = lines.split_off(first);
+        if let Some(input) = &self.input {
+            shown.push(format!("> {}_", input));
+        }
+
+        let height = shown.len().max(1) as i32 * self.line_height + PADDING * 2;
+        let background = Rect::new(
+            area.x(),
+            area.bottom() - height,
+            area.width(),
+            height as u32,
+        );
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0x0, 0x0, 0x0, 0xc0));
+        canvas.fill_rect(background)?;
+        canvas.set_blend_mode(BlendMode::None);
+
+        let mut y = background.y() + PADDING;
+        for line in &shown {
+            self.texture_manager
+                .draw_text(canvas, GLYPHS, line, area.x() + PADDING, y)?;
+            y += self.line_height;
+        }
+        Ok(())
+    }
+
+    // breaks a line between words so that each part fits in `width`
+    fn wrap(&self, line: &str, width: i32) -> Vec<String> {
+        let mut wrapped = vec![];
+        let mut current = String::new();
+        for word in line.split(' ') {
+            let candidate = if current.is_empty() {
+                String::from(word)
+            } else {
+                format!("{} {}", current, word)
+            };
+            if self.texture_manager.text_width(GLYPHS, &candidate) as i32 > width
+                && !current.is_empty()
+            {
+                wrapped.push(current);
+                current = String::from(word);
+            } else {
+                current = candidate;
+            }
+        }
+        wrapped.push(current);
+        wrapped
+    }
+}
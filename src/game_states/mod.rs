@@ -1,7 +1,14 @@
 mod boardstate;
+mod chat;
 mod pausestate;
+mod player;
+mod reviewstate;
+mod spectatorstate;
 mod winstate;
 
 pub use boardstate::BoardState;
 pub use pausestate::PauseState;
+pub use player::Player;
+pub use reviewstate::ReviewState;
+pub use spectatorstate::SpectatorState;
 pub use winstate::WinState;
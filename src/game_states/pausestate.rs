@@ -60,7 +60,7 @@ impl<'ttf> GameStateTrait for PauseState<'ttf> {
                 keycode: Some(Keycode::Escape),
                 ..
             } => Ok(RuntimeSignal::GotoState(0)),
-            _ => Ok(RuntimeSignal::Continue)
+            _ => Ok(RuntimeSignal::Continue),
         }
     }
 
@@ -0,0 +1,30 @@
+use checkers::engine::Worker;
+
+pub enum Player {
+    Human,
+    Computer(Worker),
+    /// The opponent in a network game, whose moves arrive over the connection.
+    Remote,
+}
+
+impl Player {
+    pub fn is_human(&self) -> bool {
+        match self {
+            Player::Human => true,
+            Player::Computer(..) | Player::Remote => false,
+        }
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        match self {
+            Player::Human | Player::Remote => false,
+            Player::Computer(worker) => worker.is_thinking(),
+        }
+    }
+
+    pub fn cancel_thinking(&mut self) {
+        if let Player::Computer(worker) = self {
+            worker.cancel();
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use crate::asset_loader::{Assets, TextureManager, GLYPH_COUNT};
+use crate::game_events::ReviewEvent;
+use crate::game_machine::runtime_signal::RuntimeSignal;
+use crate::game_machine::state::GameStateTrait;
+use checkers::engine::{analyse, Judgement, MoveReview, Searcher, MAN_VALUE};
+use checkers::notation::write_move;
+use checkers::rules::{Board, Move, Position, Side, BOARD_LENGTH, BOARD_SIZE};
+use checkers::tablebase::Tablebase;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use sdl2::EventSubsystem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// the board is drawn smaller than while playing, to leave room for the review below it
+const TILE_WIDTH: i32 = 80;
+const CHECKER_PADDING: i32 = 16;
+const OUTER_PADDING: i32 = 20;
+
+const ANALYSIS_DEPTH: u32 = 12;
+const ANALYSIS_TIME: Duration = Duration::from_secs(1);
+
+const TEXT_GLYPHS: usize = 0;
+const WARNING_GLYPHS: usize = TEXT_GLYPHS + GLYPH_COUNT;
+
+struct Analysis {
+    stop: Arc<AtomicBool>,
+    receiver: Receiver<MoveReview>,
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Steps through a finished game, showing the engine's choice next to every played move.
+pub struct ReviewState<'ttf> {
+    is_set_up: bool,
+    texture_manager: TextureManager<'ttf>,
+    tablebase: Option<Arc<Tablebase>>,
+    // every position of the game, the one after the last move included
+    positions: Vec<Board>,
+    moves: Vec<Move>,
+    reviews: Vec<MoveReview>,
+    analysis: Option<Analysis>,
+    ply: usize,
+    line_height: i32,
+}
+
+impl<'ttf> ReviewState<'ttf> {
+    pub fn new(text_creator: &'ttf TextureCreator<WindowContext>) -> ReviewState<'ttf> {
+        ReviewState {
+            is_set_up: false,
+            texture_manager: TextureManager::new(text_creator),
+            tablebase: None,
+            positions: vec![],
+            moves: vec![],
+            reviews: vec![],
+            analysis: None,
+            ply: 0,
+            line_height: 0,
+        }
+    }
+
+    pub fn with_tablebase(mut self, tablebase: Arc<Tablebase>) -> ReviewState<'ttf> {
+        self.tablebase = Some(tablebase);
+        self
+    }
+
+    // the analysis runs on its own thread and hands over each move as soon as it is reviewed
+    fn start_analysis(&mut self, event: ReviewEvent) {
+        let moves: Vec<Move> = event.game.moves().cloned().collect();
+        if moves == self.moves && !self.positions.is_empty() {
+            // back from the end screen, the review is already there
+            return;
+        }
+
+        let mut board = *event.game.start();
+        self.positions = vec![board];
+        for mv in &moves {
+            board.apply(mv);
+            self.positions.push(board);
+        }
+        self.moves = moves;
+        self.reviews.clear();
+        self.ply = 0;
+
+        let mut searcher = Searcher::new(ANALYSIS_DEPTH).with_time_limit(ANALYSIS_TIME);
+        if let Some(tablebase) = &self.tablebase {
+            searcher = searcher.with_tablebase(tablebase.clone());
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let game = event.game;
+        let thread_stop = stop.clone();
+
+        thread::spawn(move || {
+            analyse(&game, &mut searcher, &thread_stop, |review| {
+                // the review screen is gone, the rest would not be shown anyway
+                if sender.send(review.clone()).is_err() {
+                    thread_stop.store(true, Ordering::Relaxed);
+                }
+            });
+        });
+
+        self.analysis = Some(Analysis { stop, receiver });
+    }
+
+    fn is_analysing(&self) -> bool {
+        self.analysis.is_some() && self.reviews.len() < self.moves.len()
+    }
+
+    fn tile(&self, pos: Position) -> Rect {
+        let index = pos.index();
+        let x = (index % BOARD_LENGTH) as i32;
+        let y = (index / BOARD_LENGTH) as i32;
+        Rect::new(
+            OUTER_PADDING + x * TILE_WIDTH,
+            OUTER_PADDING + y * TILE_WIDTH,
+            TILE_WIDTH as u32,
+            TILE_WIDTH as u32,
+        )
+    }
+
+    fn render_board(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        let board = match self.positions.get(self.ply) {
+            Some(board) => board,
+            None => return Ok(()),
+        };
+
+        for index in 0..BOARD_SIZE {
+            let (x, y) = (index % BOARD_LENGTH, index / BOARD_LENGTH);
+            let tile = self.tile(Position::from_index(index).unwrap());
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+            canvas.draw_rect(tile)?;
+            if x % 2 != y % 2 {
+                canvas.set_draw_color(Color::RGB(0x1f, 0x1f, 0x1f));
+                canvas.fill_rect(tile)?;
+            }
+        }
+
+        // the played move is filled in, the engine's choice outlined on top so both stay visible
+        if let Some(played) = self.moves.get(self.ply) {
+            canvas.set_draw_color(Color::RGB(0x0, 0x0f, 0xfa));
+            canvas.fill_rect(self.tile(played.from))?;
+            canvas.set_draw_color(Color::RGB(0x6f, 0x8f, 0xfa));
+            for pos in &played.path {
+                canvas.fill_rect(self.tile(*pos))?;
+            }
+        }
+        if let Some(review) = self.reviews.get(self.ply) {
+            canvas.set_draw_color(Color::RGB(0xff, 0xa5, 0x0));
+            for pos in std::iter::once(&review.best.from).chain(review.best.path.iter()) {
+                let tile = self.tile(*pos);
+                for inset in 0..4 {
+                    canvas.draw_rect(Rect::new(
+                        tile.x() + inset,
+                        tile.y() + inset,
+                        tile.width() - inset as u32 * 2,
+                        tile.height() - inset as u32 * 2,
+                    ))?;
+                }
+            }
+        }
+
+        for (pos, piece) in board.pieces() {
+            let tile = self.tile(pos);
+            let checker = Rect::new(
+                tile.x() + CHECKER_PADDING,
+                tile.y() + CHECKER_PADDING,
+                (TILE_WIDTH - CHECKER_PADDING * 2) as u32,
+                (TILE_WIDTH - CHECKER_PADDING * 2) as u32,
+            );
+            match piece.side {
+                Side::Green => canvas.set_draw_color(Color::RGB(0x0, 0xff, 0x0)),
+                Side::Red => canvas.set_draw_color(Color::RGB(0xff, 0x0, 0x0)),
+            }
+            canvas.fill_rect(checker)?;
+
+            if piece.is_king() {
+                canvas.set_draw_color(Color::RGB(0xef, 0xef, 0x00));
+                canvas.fill_rect(Rect::new(checker.x(), checker.y(), 16, 16))?;
+            }
+        }
+        Ok(())
+    }
+
+    // what happened at the shown ply, each line with the glyphs it is drawn in
+    fn review_lines(&self) -> Vec<(usize, String)> {
+        let mut lines = vec![];
+        let played = match self.moves.get(self.ply) {
+            Some(played) => played,
+            None => {
+                lines.push((
+                    TEXT_GLYPHS,
+                    format!("Final position after {} plies", self.moves.len()),
+                ));
+                lines.push((TEXT_GLYPHS, self.summary()));
+                return lines;
+            }
+        };
+
+        let side = match self.positions[self.ply].turn() {
+            Side::Green => "Green",
+            Side::Red => "Red",
+        };
+        lines.push((
+            TEXT_GLYPHS,
+            format!(
+                "Ply {} of {}: {} played {}",
+                self.ply + 1,
+                self.moves.len(),
+                side,
+                write_move(played)
+            ),
+        ));
+
+        match self.reviews.get(self.ply) {
+            Some(review) if review.best == review.played => {
+                lines.push((TEXT_GLYPHS, String::from("The engine agrees")));
+            }
+            Some(review) => {
+                lines.push((
+                    TEXT_GLYPHS,
+                    format!("The engine prefers {}", write_move(&review.best)),
+                ));
+                let men = review.drop() as f32 / MAN_VALUE as f32;
+                let verdict = match review.judgement {
+                    Judgement::Blunder => Some("Blunder"),
+                    Judgement::Mistake => Some("Mistake"),
+                    Judgement::Good => None,
+                };
+                if let Some(verdict) = verdict {
+                    lines.push((
+                        WARNING_GLYPHS,
+                        format!("{}: {:.2} men worse than the engine's move", verdict, men),
+                    ));
+                }
+            }
+            None => lines.push((TEXT_GLYPHS, String::from("Analysing..."))),
+        }
+        lines.push((TEXT_GLYPHS, self.summary()));
+        lines
+    }
+
+    fn summary(&self) -> String {
+        if self.is_analysing() {
+            return format!(
+                "Analysed {} of {} moves",
+                self.reviews.len(),
+                self.moves.len()
+            );
+        }
+
+        let count = |side: Side, judgement: Judgement| {
+            self.reviews
+                .iter()
+                .zip(self.positions.iter())
+                .filter(|(review, board)| board.turn() == side && review.judgement == judgement)
+                .count()
+        };
+        format!(
+            "Green: {} mistakes, {} blunders. Red: {} mistakes, {} blunders",
+            count(Side::Green, Judgement::Mistake),
+            count(Side::Green, Judgement::Blunder),
+            count(Side::Red, Judgement::Mistake),
+            count(Side::Red, Judgement::Blunder),
+        )
+    }
+}
+
+impl GameStateTrait for ReviewState<'_> {
+    fn update(&mut self, _event: &EventSubsystem) -> Result<RuntimeSignal, String> {
+        if let Some(analysis) = &self.analysis {
+            self.reviews.extend(analysis.receiver.try_iter());
+        }
+        Ok(RuntimeSignal::Continue)
+    }
+
+    fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        canvas.set_draw_color(Color::RGB(0xff, 0xff, 0xff));
+        canvas.clear();
+
+        self.render_board(canvas)?;
+
+        let mut y = OUTER_PADDING + TILE_WIDTH * BOARD_LENGTH as i32 + 12;
+        for (glyphs, line) in self.review_lines() {
+            self.texture_manager
+                .draw_text(canvas, glyphs, &line, OUTER_PADDING, y)?;
+            y += self.line_height;
+        }
+        self.texture_manager.draw_text(
+            canvas,
+            TEXT_GLYPHS,
+            "Left and Right step through the game, Escape goes back",
+            OUTER_PADDING,
+            y + self.line_height,
+        )?;
+
+        canvas.present();
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<RuntimeSignal, String> {
+        if event.is_user_event() {
+            if let Some(review) = event.as_user_event_type::<ReviewEvent>() {
+                self.start_analysis(review);
+            }
+        }
+
+        match event {
+            Event::Quit { .. } => Ok(RuntimeSignal::Quit),
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => Ok(RuntimeSignal::GotoState(1)),
+            Event::KeyDown {
+                keycode: Some(Keycode::Left),
+                ..
+            } => {
+                self.ply = self.ply.saturating_sub(1);
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Right),
+                ..
+            } => {
+                self.ply = (self.ply + 1).min(self.moves.len());
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Home),
+                ..
+            } => {
+                self.ply = 0;
+                Ok(RuntimeSignal::Continue)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::End),
+                ..
+            } => {
+                self.ply = self.moves.len();
+                Ok(RuntimeSignal::Continue)
+            }
+            _ => Ok(RuntimeSignal::Continue),
+        }
+    }
+
+    fn setup(&mut self, ass: &Assets) -> Result<(), String> {
+        if let Some(font_with_info) = ass.font_collection.b612_regular.get(&18) {
+            let font = font_with_info.font_ref();
+            self.line_height = font_with_info.font_size() as i32 + 10;
+            self.texture_manager
+                .insert_glyphs(TEXT_GLYPHS, font, Color::RGB(0x0, 0x0, 0xaf))?;
+            self.texture_manager
+                .insert_glyphs(WARNING_GLYPHS, font, Color::RGB(0xaf, 0x0, 0x0))?;
+        }
+
+        self.is_set_up = true;
+        Ok(())
+    }
+
+    fn is_set_up(&self) -> bool {
+        self.is_set_up
+    }
+}
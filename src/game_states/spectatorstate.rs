@@ -0,0 +1,241 @@
+use crate::asset_loader::{Assets, TextureManager, GLYPH_COUNT};
+use crate::game_events::GameOverEvent;
+use crate::game_machine::runtime_signal::RuntimeSignal;
+use crate::game_machine::state::GameStateTrait;
+use crate::game_states::chat::ChatOverlay;
+use checkers::net::{NetEvent, Spectator};
+use checkers::notation::write_move;
+use checkers::rules::{Position, Side, BOARD_LENGTH, BOARD_SIZE};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use sdl2::EventSubsystem;
+
+// the board is drawn smaller than while playing, to leave room for the moves beside it
+const TILE_WIDTH: i32 = 80;
+const CHECKER_PADDING: i32 = 16;
+const OUTER_PADDING: i32 = 20;
+const MOVE_LIST_X: i32 = OUTER_PADDING * 2 + TILE_WIDTH * BOARD_LENGTH as i32;
+
+const TEXT_GLYPHS: usize = 0;
+const MOVE_GLYPHS: usize = TEXT_GLYPHS + GLYPH_COUNT;
+
+/// Follows a hosted game as it is played, with the moves so far beside the board and the
+/// players' chat over it. Nothing on the board can be clicked.
+pub struct SpectatorState<'ttf> {
+    is_set_up: bool,
+    texture_manager: TextureManager<'ttf>,
+    spectator: Spectator,
+    chat: ChatOverlay<'ttf>,
+    line_height: i32,
+    move_height: i32,
+}
+
+impl<'ttf> SpectatorState<'ttf> {
+    pub fn new(
+        text_creator: &'ttf TextureCreator<WindowContext>,
+        spectator: Spectator,
+    ) -> SpectatorState<'ttf> {
+        SpectatorState {
+            is_set_up: false,
+            texture_manager: TextureManager::new(text_creator),
+            spectator,
+            chat: ChatOverlay::new(text_creator),
+            line_height: 0,
+            move_height: 0,
+        }
+    }
+
+    fn tile(&self, pos: Position) -> Rect {
+        let index = pos.index();
+        let x = (index % BOARD_LENGTH) as i32;
+        let y = (index / BOARD_LENGTH) as i32;
+        Rect::new(
+            OUTER_PADDING + x * TILE_WIDTH,
+            OUTER_PADDING + y * TILE_WIDTH,
+            TILE_WIDTH as u32,
+            TILE_WIDTH as u32,
+        )
+    }
+
+    fn render_board(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        let game = self.spectator.game();
+
+        for index in 0..BOARD_SIZE {
+            let (x, y) = (index % BOARD_LENGTH, index / BOARD_LENGTH);
+            let tile = self.tile(Position::from_index(index).unwrap());
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+            canvas.draw_rect(tile)?;
+            if x % 2 != y % 2 {
+                canvas.set_draw_color(Color::RGB(0x1f, 0x1f, 0x1f));
+                canvas.fill_rect(tile)?;
+            }
+        }
+
+        if let Some(last) = game.moves().last() {
+            canvas.set_draw_color(Color::RGB(0x0, 0x0f, 0xfa));
+            canvas.fill_rect(self.tile(last.from))?;
+            canvas.set_draw_color(Color::RGB(0x6f, 0x8f, 0xfa));
+            for pos in &last.path {
+                canvas.fill_rect(self.tile(*pos))?;
+            }
+        }
+
+        for (pos, piece) in game.board().pieces() {
+            let tile = self.tile(pos);
+            let checker = Rect::new(
+                tile.x() + CHECKER_PADDING,
+                tile.y() + CHECKER_PADDING,
+                (TILE_WIDTH - CHECKER_PADDING * 2) as u32,
+                (TILE_WIDTH - CHECKER_PADDING * 2) as u32,
+            );
+            match piece.side {
+                Side::Green => canvas.set_draw_color(Color::RGB(0x0, 0xff, 0x0)),
+                Side::Red => canvas.set_draw_color(Color::RGB(0xff, 0x0, 0x0)),
+            }
+            canvas.fill_rect(checker)?;
+
+            if piece.is_king() {
+                canvas.set_draw_color(Color::RGB(0xef, 0xef, 0x00));
+                canvas.fill_rect(Rect::new(checker.x(), checker.y(), 16, 16))?;
+            }
+        }
+        Ok(())
+    }
+
+    // one line per move number, green's ply first, as in a score sheet
+    fn move_lines(&self) -> Vec<String> {
+        let game = self.spectator.game();
+        let mut lines: Vec<String> = vec![];
+        let mut number = 1;
+        let mut turn = game.start().turn();
+        if turn == Side::Red {
+            lines.push(String::from("1. ..."));
+        }
+
+        for mv in game.moves() {
+            match turn {
+                Side::Green => lines.push(format!("{}. {}", number, write_move(mv))),
+                Side::Red => {
+                    if let Some(line) = lines.last_mut() {
+                        line.push(' ');
+                        line.push_str(&write_move(mv));
+                    }
+                    number += 1;
+                }
+            }
+            turn = turn.opponent();
+        }
+        lines
+    }
+
+    fn status(&self) -> &'static str {
+        if !self.spectator.is_connected() {
+            return "The host has left the game";
+        }
+        match self.spectator.game().board().turn() {
+            Side::Green => "Watching, green to move",
+            Side::Red => "Watching, red to move",
+        }
+    }
+}
+
+impl GameStateTrait for SpectatorState<'_> {
+    fn update(&mut self, event: &EventSubsystem) -> Result<RuntimeSignal, String> {
+        // the moves are played on the spectator's own game, only the end needs looking at
+        while let Some(event) = self.spectator.poll()? {
+            if let NetEvent::Chat { name, text } = event {
+                self.chat.push(&name, &text);
+            }
+        }
+
+        match self.spectator.game().outcome() {
+            Some(outcome) => {
+                let game = self.spectator.game().clone();
+                event.push_custom_event(GameOverEvent::new(outcome, 0, game))?;
+                Ok(RuntimeSignal::GotoState(1))
+            }
+            None => Ok(RuntimeSignal::Continue),
+        }
+    }
+
+    fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        canvas.set_draw_color(Color::RGB(0xff, 0xff, 0xff));
+        canvas.clear();
+
+        self.render_board(canvas)?;
+
+        let y = OUTER_PADDING + TILE_WIDTH * BOARD_LENGTH as i32 + 12;
+        self.texture_manager
+            .draw_text(canvas, TEXT_GLYPHS, self.status(), OUTER_PADDING, y)?;
+        self.texture_manager.draw_text(
+            canvas,
+            TEXT_GLYPHS,
+            "Spectating, the board cannot be played on",
+            OUTER_PADDING,
+            y + self.line_height,
+        )?;
+
+        // the latest moves, as many as fit
+        let lines = self.move_lines();
+        let fitting = ((canvas.viewport().height() as i32 - OUTER_PADDING * 2)
+            / self.move_height.max(1)) as usize;
+        let mut y = OUTER_PADDING;
+        for line in lines.iter().skip(lines.len().saturating_sub(fitting)) {
+            self.texture_manager
+                .draw_text(canvas, MOVE_GLYPHS, line, MOVE_LIST_X, y)?;
+            y += self.move_height;
+        }
+
+        let board_width = (TILE_WIDTH * BOARD_LENGTH as i32) as u32;
+        self.chat.render(
+            canvas,
+            Rect::new(OUTER_PADDING, OUTER_PADDING, board_width, board_width),
+        )?;
+
+        canvas.present();
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<RuntimeSignal, String> {
+        match event {
+            Event::Quit { .. } => Ok(RuntimeSignal::Quit),
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => Ok(RuntimeSignal::GotoState(2)),
+            _ => Ok(RuntimeSignal::Continue),
+        }
+    }
+
+    fn setup(&mut self, ass: &Assets) -> Result<(), String> {
+        if let Some(font_with_info) = ass.font_collection.b612_regular.get(&18) {
+            self.line_height = font_with_info.font_size() as i32 + 10;
+            self.texture_manager.insert_glyphs(
+                TEXT_GLYPHS,
+                font_with_info.font_ref(),
+                Color::RGB(0x0, 0x0, 0xaf),
+            )?;
+        }
+
+        if let Some(font_with_info) = ass.font_collection.vt323_regular.get(&18) {
+            self.move_height = font_with_info.font_size() as i32 + 4;
+            self.texture_manager.insert_glyphs(
+                MOVE_GLYPHS,
+                font_with_info.font_ref(),
+                Color::RGB(0x0, 0x0, 0x0),
+            )?;
+        }
+        self.chat.setup(ass)?;
+
+        self.is_set_up = true;
+        Ok(())
+    }
+
+    fn is_set_up(&self) -> bool {
+        self.is_set_up
+    }
+}
@@ -9,17 +9,25 @@ use sdl2::render::{Canvas, TextureCreator, TextureQuery};
 use sdl2::video::{Window, WindowContext};
 
 use crate::asset_loader::{Assets, TextureManager};
-use crate::game_events::WinColorEvent;
+use crate::game_events::{GameOverEvent, ReviewEvent};
+use checkers::rules::{DrawReason, Game, Outcome, Side};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 
 const GREEN_TEXT_WIN: usize = 0;
 const RED_TEXT_WIN: usize = 1;
+const REPETITION_TEXT_DRAW: usize = 2;
+const MOVE_LIMIT_TEXT_DRAW: usize = 3;
+const AGREEMENT_TEXT_DRAW: usize = 4;
+const GLYPHS: usize = 5;
 
 pub struct WinState<'ttf> {
     texture_manager: TextureManager<'ttf>,
     is_set_up: bool,
-    is_green_win: bool,
+    outcome: Option<Outcome>,
+    hints: u32,
+    game: Option<Game>,
+    wants_review: bool,
 }
 
 impl<'ttf> WinState<'ttf> {
@@ -27,43 +35,67 @@ impl<'ttf> WinState<'ttf> {
         WinState {
             texture_manager: TextureManager::new(text_creator),
             is_set_up: false,
-            is_green_win: false,
+            outcome: None,
+            hints: 0,
+            game: None,
+            wants_review: false,
         }
     }
 }
 
+impl<'ttf> WinState<'ttf> {
+    fn render_centered(
+        &self,
+        canvas: &mut Canvas<Window>,
+        text: &str,
+        y: i32,
+    ) -> Result<(), String> {
+        let width = self.texture_manager.text_width(GLYPHS, text);
+        let x = canvas.viewport().center().x() - (width as i32 / 2);
+        self.texture_manager.draw_text(canvas, GLYPHS, text, x, y)
+    }
+}
+
 impl<'a> GameStateTrait for WinState<'a> {
-    fn update(&mut self, _event: &sdl2::EventSubsystem) -> Result<RuntimeSignal, String> {
-        Ok(RuntimeSignal::Continue)
+    fn update(&mut self, event: &sdl2::EventSubsystem) -> Result<RuntimeSignal, String> {
+        match &self.game {
+            Some(game) if self.wants_review => {
+                self.wants_review = false;
+                event.push_custom_event(ReviewEvent::new(game.clone()))?;
+
+                Ok(RuntimeSignal::GotoState(3))
+            }
+            _ => Ok(RuntimeSignal::Continue),
+        }
     }
 
     fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
         canvas.set_draw_color(Color::RGB(0xff, 0xff, 0xff));
         canvas.clear();
 
-        if self.is_green_win {
-            if let Some(txtr) = self.texture_manager.get_texture(GREEN_TEXT_WIN) {
-                let center = canvas.viewport().center();
-                let TextureQuery { width, height, .. } = txtr.get_texture_info_ref();
-
-                let half_x = center.x() - (*width as i32 / 2);
-                let half_y = center.y() - (*height as i32 / 2);
-
-                let dst = Some(Rect::new(half_x, half_y, *width, *height));
+        let text = match self.outcome {
+            Some(Outcome::Win(Side::Green)) => Some(GREEN_TEXT_WIN),
+            Some(Outcome::Win(Side::Red)) => Some(RED_TEXT_WIN),
+            Some(Outcome::Draw(DrawReason::Repetition)) => Some(REPETITION_TEXT_DRAW),
+            Some(Outcome::Draw(DrawReason::MoveLimit)) => Some(MOVE_LIMIT_TEXT_DRAW),
+            Some(Outcome::Draw(DrawReason::Agreement)) => Some(AGREEMENT_TEXT_DRAW),
+            None => None,
+        };
 
-                canvas.copy(txtr.get_texture_ref(), None, dst)?;
-            }
-        } else {
-            if let Some(txtr) = self.texture_manager.get_texture(RED_TEXT_WIN) {
-                let center = canvas.viewport().center();
-                let TextureQuery { width, height, .. } = txtr.get_texture_info_ref();
+        if let Some(txtr) = text.and_then(|i| self.texture_manager.get_texture(i)) {
+            let center = canvas.viewport().center();
+            let TextureQuery { width, height, .. } = txtr.get_texture_info_ref();
 
-                let half_x = center.x() - (*width as i32 / 2);
-                let half_y = center.y() - (*height as i32 / 2);
+            let half_x = center.x() - (*width as i32 / 2);
+            let half_y = center.y() - (*height as i32 / 2);
 
-                let dst = Some(Rect::new(half_x, half_y, *width, *height));
+            let dst = Some(Rect::new(half_x, half_y, *width, *height));
 
-                canvas.copy(txtr.get_texture_ref(), None, dst)?;
+            canvas.copy(txtr.get_texture_ref(), None, dst)?;
+            let below = half_y + *height as i32 + 20;
+            self.render_centered(canvas, &format!("Hints used: {}", self.hints), below)?;
+            if self.game.is_some() {
+                self.render_centered(canvas, "Press R to review the game", below + 40)?;
             }
         }
 
@@ -78,15 +110,18 @@ impl<'a> GameStateTrait for WinState<'a> {
                 keycode: Some(Keycode::Escape),
                 ..
             } => return Ok(RuntimeSignal::Quit),
+            Event::KeyDown {
+                keycode: Some(Keycode::R),
+                ..
+            } => self.wants_review = true,
             _ => {}
         };
 
         if event.is_user_event() {
-            match &event.as_user_event_type::<WinColorEvent>() {
-                Some(wce) => {
-                    self.is_green_win = wce.is_green();
-                }
-                _ => {}
+            if let Some(goe) = event.as_user_event_type::<GameOverEvent>() {
+                self.outcome = Some(goe.outcome);
+                self.hints = goe.hints;
+                self.game = Some(goe.game);
             }
         }
 
@@ -110,6 +145,24 @@ impl<'a> GameStateTrait for WinState<'a> {
                 .map_err(|e| e.to_string())?,
         )?;
 
+        let draw_texts = [
+            (REPETITION_TEXT_DRAW, "Draw by repetition"),
+            (MOVE_LIMIT_TEXT_DRAW, "Draw by the move limit"),
+            (AGREEMENT_TEXT_DRAW, "Draw agreed"),
+        ];
+        for (key, text) in draw_texts.iter() {
+            self.texture_manager.insert_surface_as_texture(
+                *key,
+                font.render(text)
+                    .blended(Color::RGB(0x0, 0x0, 0xaf))
+                    .map_err(|e| e.to_string())?,
+            )?;
+        }
+
+        let font = ass.font_collection.share_tech_mono_regular[&30].font_ref();
+        self.texture_manager
+            .insert_glyphs(GLYPHS, font, Color::RGB(0x0, 0x0, 0x0))?;
+
         self.is_set_up = true;
         Ok(())
     }
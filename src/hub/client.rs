@@ -0,0 +1,247 @@
+use crate::engine::{Engine, SearchResult, MAN_VALUE};
+use crate::hub::{parse_move, write_move, write_position, Message};
+use crate::rules::{variant_name, Game, Rules};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// how long an engine may take to start up and answer the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// how often the stop flag is looked at while the engine thinks
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// An external engine process driven through the Hub protocol.
+pub struct HubEngine {
+    child: Child,
+    input: ChildStdin,
+    lines: Receiver<String>,
+    name: String,
+    move_time: Option<Duration>,
+    depth: Option<u32>,
+}
+
+impl HubEngine {
+    /// Starts `program` and goes through the handshake, choosing the variant of `rules`.
+    pub fn launch(program: &Path, rules: Rules) -> Result<HubEngine, String> {
+        let mut child = Command::new(program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Could not start {}: {}", program.display(), e))?;
+        let input = child.stdin.take().unwrap();
+        let output = child.stdout.take().unwrap();
+
+        // lines are read on their own thread so that waiting for them can time out
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(output).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = HubEngine {
+            child,
+            input,
+            lines,
+            name: program.display().to_string(),
+            move_time: None,
+            depth: None,
+        };
+        engine.handshake(rules)?;
+        Ok(engine)
+    }
+
+    /// Asks for every move to be played within `move_time`.
+    pub fn with_move_time(mut self, move_time: Duration) -> HubEngine {
+        self.move_time = Some(move_time);
+        self
+    }
+
+    pub fn with_depth(mut self, depth: u32) -> HubEngine {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// The name the engine gave itself.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handshake(&mut self, rules: Rules) -> Result<(), String> {
+        self.send(&Message::new("hub"))?;
+
+        let mut variants = None;
+        loop {
+            let message = self.receive_in_time()?;
+            match message.command.as_ref() {
+                "id" => {
+                    if let Some(name) = message.get("name") {
+                        self.name = String::from(name);
+                    }
+                }
+                "param" if message.get("name") == Some("variant") => {
+                    variants = Some(message.get("values").unwrap_or("").to_string())
+                }
+                "wait" => break,
+                _ => {}
+            }
+        }
+
+        // engines without the parameter are taken to play the normal game only
+        let variant = variant_name(rules);
+        match variants {
+            Some(values) if values.split_whitespace().any(|value| value == variant) => self.send(
+                &Message::new("set-param")
+                    .with("name", "variant")
+                    .with("value", variant),
+            )?,
+            _ if rules == Rules::default() => {}
+            _ => {
+                return Err(format!(
+                    "{} does not play the {} variant",
+                    self.name, variant
+                ))
+            }
+        }
+
+        self.send(&Message::new("init"))?;
+        loop {
+            if self.receive_in_time()?.command == "ready" {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), String> {
+        writeln!(self.input, "{}", message)
+            .and_then(|_| self.input.flush())
+            .map_err(|e| format!("{} stopped listening: {}", self.name, e))
+    }
+
+    // unreadable lines are skipped, the protocol asks to ignore what is not understood
+    fn receive(&mut self, timeout: Duration) -> Result<Option<Message>, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(left) {
+                Ok(line) => {
+                    if let Ok(message) = Message::parse(&line) {
+                        return Ok(Some(message));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("{} has quit", self.name))
+                }
+            }
+        }
+    }
+
+    fn receive_in_time(&mut self) -> Result<Message, String> {
+        self.receive(HANDSHAKE_TIMEOUT)?
+            .ok_or_else(|| format!("{} did not answer in time", self.name))
+    }
+
+    fn think(&mut self, game: &Game, stop: &AtomicBool) -> Result<Option<SearchResult>, String> {
+        let mut result = SearchResult {
+            best: match game.board().legal_moves().into_iter().next() {
+                Some(mv) => mv,
+                None => return Ok(None),
+            },
+            score: 0,
+            depth: 0,
+            nodes: 0,
+        };
+
+        let moves: Vec<String> = game.moves().map(write_move).collect();
+        let mut position = Message::new("pos").with("pos", &write_position(game.start()));
+        if !moves.is_empty() {
+            position = position.with("moves", &moves.join(" "));
+        }
+        self.send(&position)?;
+
+        let mut level = Message::new("level");
+        if let Some(move_time) = self.move_time {
+            level = level.with("move-time", &format!("{:.3}", move_time.as_secs_f64()));
+        }
+        if let Some(depth) = self.depth {
+            level = level.with("depth", &depth.to_string());
+        }
+        if !level.args.is_empty() {
+            self.send(&level)?;
+        }
+        self.send(&Message::new("go").with_flag("think"))?;
+
+        let mut is_stopping = false;
+        loop {
+            if !is_stopping && stop.load(Ordering::Relaxed) {
+                // the engine still answers, with the best move it has found so far
+                self.send(&Message::new("stop"))?;
+                is_stopping = true;
+            }
+
+            let message = match self.receive(POLL_INTERVAL)? {
+                Some(message) => message,
+                None => continue,
+            };
+            match message.command.as_ref() {
+                "info" => {
+                    if let Some(depth) = message.get("depth").and_then(|d| d.parse().ok()) {
+                        result.depth = depth;
+                    }
+                    if let Some(nodes) = message.get("nodes").and_then(|n| n.parse().ok()) {
+                        result.nodes = nodes;
+                    }
+                    // scores are given in men
+                    if let Some(score) = message.get("score").and_then(|s| s.parse::<f64>().ok()) {
+                        result.score = (score * MAN_VALUE as f64) as i32;
+                    }
+                }
+                "done" => {
+                    let text = message
+                        .get("move")
+                        .ok_or_else(|| format!("{} gave no move", self.name))?;
+                    result.best = parse_move(game.board(), text)
+                        .map_err(|e| format!("{} played badly: {}", self.name, e))?;
+                    return Ok(Some(result));
+                }
+                "error" => {
+                    return Err(format!(
+                        "{}: {}",
+                        self.name,
+                        message.get("message").unwrap_or("unknown error")
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Engine for HubEngine {
+    fn search(&mut self, game: &Game, stop: &AtomicBool) -> Result<Option<SearchResult>, String> {
+        self.think(game, stop)
+    }
+}
+
+impl Drop for HubEngine {
+    fn drop(&mut self) {
+        let _ = self.send(&Message::new("quit"));
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
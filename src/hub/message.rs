@@ -0,0 +1,99 @@
+use std::fmt;
+
+/// One line of the Hub protocol: a command followed by `name=value` arguments, values with
+/// spaces in quotes, and bare flags such as the `think` of `go think`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Message {
+    pub command: String,
+    pub args: Vec<(String, Option<String>)>,
+}
+
+impl Message {
+    pub fn new(command: &str) -> Message {
+        Message {
+            command: String::from(command),
+            args: vec![],
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: &str) -> Message {
+        self.args
+            .push((String::from(name), Some(String::from(value))));
+        self
+    }
+
+    pub fn with_flag(mut self, name: &str) -> Message {
+        self.args.push((String::from(name), None));
+        self
+    }
+
+    pub fn parse(line: &str) -> Result<Message, String> {
+        let mut rest = line.trim();
+        let command = take_word(&mut rest);
+        if command.is_empty() {
+            return Err(String::from("Empty message"));
+        }
+
+        let mut message = Message::new(command);
+        while !rest.is_empty() {
+            let name_end = rest
+                .find(|c: char| c == '=' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            let name = &rest[..name_end];
+            rest = &rest[name_end..];
+
+            if let Some(after) = rest.strip_prefix('=') {
+                let value = if let Some(quoted) = after.strip_prefix('"') {
+                    let end = quoted
+                        .find('"')
+                        .ok_or_else(|| format!("Unterminated value in '{}'", line))?;
+                    rest = &quoted[end + 1..];
+                    &quoted[..end]
+                } else {
+                    rest = after;
+                    take_word(&mut rest)
+                };
+                message = message.with(name, value);
+            } else {
+                message = message.with_flag(name);
+            }
+            rest = rest.trim_start();
+        }
+        Ok(message)
+    }
+
+    /// The value of argument `name`, `None` when it is missing or a flag.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(known, _)| known == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.args.iter().any(|(known, _)| known == name)
+    }
+}
+
+fn take_word<'a>(text: &mut &'a str) -> &'a str {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let (word, rest) = text.split_at(end);
+    *text = rest.trim_start();
+    word
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.command)?;
+        for (name, value) in &self.args {
+            match value {
+                Some(value) if value.is_empty() || value.contains(char::is_whitespace) => {
+                    write!(f, " {}=\"{}\"", name, value)?
+                }
+                Some(value) => write!(f, " {}={}", name, value)?,
+                None => write!(f, " {}", name)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! The Hub protocol spoken by draughts engines such as Scan: one text message per line over
+//! the engine's standard input and output. `HubEngine` drives such an engine, `HubServer` lets
+//! our own engine be driven.
+
+mod client;
+mod message;
+mod server;
+
+pub use client::HubEngine;
+pub use message::Message;
+pub use server::HubServer;
+
+use crate::rules::{Board, Move, Piece, Position, Side, BOARD_SIZE};
+
+const SQUARES: usize = BOARD_SIZE / 2;
+
+/// Writes `board` as the side to move followed by one letter per square: `w` and `b` for men,
+/// `W` and `B` for kings and `e` for empty squares. Black is green and white is red.
+pub fn write_position(board: &Board) -> String {
+    let mut text = String::from(match board.turn() {
+        Side::Green => "B",
+        Side::Red => "W",
+    });
+    for square in 1..=SQUARES {
+        let piece = Position::from_square(square).and_then(|pos| board.piece_at(pos));
+        text.push(match piece {
+            Some(piece) if piece == Piece::man(Side::Green) => 'b',
+            Some(piece) if piece == Piece::man(Side::Red) => 'w',
+            Some(piece) if piece.side == Side::Green => 'B',
+            Some(_) => 'W',
+            None => 'e',
+        });
+    }
+    text
+}
+
+pub fn parse_position(text: &str) -> Result<Board, String> {
+    let mut letters = text.trim().chars();
+    let turn = match letters.next() {
+        Some('B') => Side::Green,
+        Some('W') => Side::Red,
+        _ => return Err(format!("Invalid side to move in position '{}'", text)),
+    };
+
+    let mut board = Board::empty(turn);
+    let mut square = 0;
+    for letter in letters {
+        square += 1;
+        let pos = Position::from_square(square)
+            .ok_or_else(|| format!("Position '{}' has too many squares", text))?;
+        let piece = match letter {
+            'b' => Piece::man(Side::Green),
+            'w' => Piece::man(Side::Red),
+            'B' => Piece::king(Side::Green),
+            'W' => Piece::king(Side::Red),
+            'e' => continue,
+            _ => {
+                return Err(format!(
+                    "Invalid square '{}' in position '{}'",
+                    letter, text
+                ))
+            }
+        };
+        board.set_piece(pos, Some(piece));
+    }
+
+    if square != SQUARES {
+        return Err(format!("Position '{}' has too few squares", text));
+    }
+    Ok(board)
+}
+
+/// Writes `mv` as `11-15`, or for captures as the start and end square followed by every
+/// captured square, `9x27x14x23`, which tells apart captures with the same landing squares.
+pub fn write_move(mv: &Move) -> String {
+    let square = |pos: Position| pos.square().unwrap_or(0).to_string();
+    if !mv.is_capture() {
+        return format!("{}-{}", square(mv.from), square(mv.to()));
+    }
+
+    let mut text = format!("{}x{}", square(mv.from), square(mv.to()));
+    for (pos, _) in &mv.captured {
+        text.push('x');
+        text.push_str(&square(*pos));
+    }
+    text
+}
+
+/// Finds the legal move of `board` written as by `write_move`. The captured squares may be left
+/// out as long as the move is not ambiguous.
+pub fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    let is_capture = text.contains('x');
+    let squares = text
+        .split(['-', 'x'])
+        .map(|square| {
+            square
+                .parse::<usize>()
+                .ok()
+                .and_then(Position::from_square)
+                .ok_or_else(|| format!("Invalid square '{}' in move '{}'", square, text))
+        })
+        .collect::<Result<Vec<Position>, String>>()?;
+
+    if squares.len() < 2 || (!is_capture && squares.len() > 2) {
+        return Err(format!("Invalid move '{}'", text));
+    }
+    let captured = &squares[2..];
+
+    let mut matching = board.legal_moves().into_iter().filter(|mv| {
+        mv.from == squares[0]
+            && mv.to() == squares[1]
+            && mv.is_capture() == is_capture
+            && (captured.is_empty()
+                || (captured.len() == mv.captured.len()
+                    && mv.captured.iter().all(|(pos, _)| captured.contains(pos))))
+    });
+
+    match (matching.next(), matching.next()) {
+        (Some(mv), None) => Ok(mv),
+        (Some(_), Some(_)) => Err(format!("Ambiguous move '{}'", text)),
+        (None, _) => Err(format!("Illegal move '{}'", text)),
+    }
+}
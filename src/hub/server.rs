@@ -0,0 +1,530 @@
+use crate::engine::{SearchResult, Worker, MAN_VALUE};
+use crate::hub::{parse_move, parse_position, write_move, Message};
+use crate::rules::{variant_rules, Board, Game, Rules, VARIANTS};
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// how often the running search is looked at between messages
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// moves the remaining time is spread over when the time control does not say
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+const DEFAULT_MAX_DEPTH: u32 = 64;
+
+/// The limits of the last `level` message, which replace all earlier ones.
+#[derive(Debug, Clone, Default)]
+struct Level {
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    /// Moves per time control, 0 when the time is for the whole game.
+    moves: u32,
+    time: Option<Duration>,
+    increment: Duration,
+    infinite: bool,
+}
+
+struct Thinking {
+    started: Instant,
+    deadline: Option<Instant>,
+    /// The move is only told after `ponder-hit` or `stop`.
+    pondering: bool,
+    result: Option<SearchResult>,
+}
+
+/// Serves an engine to a GUI through the Hub protocol. The engine runs without limits of its
+/// own, the time control is kept here by stopping the search once its time is up.
+pub struct HubServer<W: Write> {
+    worker: Worker,
+    output: W,
+    name: String,
+    max_depth: u32,
+    rules: Rules,
+    game: Game,
+    level: Level,
+    time_left: Option<Duration>,
+    thinking: Option<Thinking>,
+}
+
+impl<W: Write> HubServer<W> {
+    pub fn new(worker: Worker, output: W) -> HubServer<W> {
+        HubServer {
+            worker,
+            output,
+            name: String::from(env!("CARGO_PKG_NAME")),
+            max_depth: DEFAULT_MAX_DEPTH,
+            rules: Rules::default(),
+            game: Game::default(),
+            level: Level::default(),
+            time_left: None,
+            thinking: None,
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> HubServer<W> {
+        self.name = String::from(name);
+        self
+    }
+
+    /// The depth searched when the GUI does not ask for one.
+    pub fn with_max_depth(mut self, depth: u32) -> HubServer<W> {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Answers the messages read from `input` until it ends or the GUI says `quit`.
+    pub fn run<R: BufRead + Send + 'static>(mut self, input: R) -> Result<(), String> {
+        // lines are read on their own thread so that the search can be watched meanwhile
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in input.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            match lines.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => {
+                    // the protocol asks to ignore what is not understood
+                    let message = match Message::parse(&line) {
+                        Ok(message) => message,
+                        Err(_) => continue,
+                    };
+                    if message.command == "quit" {
+                        break;
+                    }
+                    if let Err(err) = self.handle(&message) {
+                        self.send(&Message::new("error").with("message", &err))?;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            self.watch_thinking()?;
+        }
+
+        self.worker.cancel();
+        Ok(())
+    }
+
+    fn handle(&mut self, message: &Message) -> Result<(), String> {
+        match message.command.as_ref() {
+            "hub" => {
+                self.send(
+                    &Message::new("id")
+                        .with("name", &self.name.clone())
+                        .with("version", env!("CARGO_PKG_VERSION")),
+                )?;
+                let values: Vec<&str> = VARIANTS.iter().map(|(name, _)| *name).collect();
+                self.send(
+                    &Message::new("param")
+                        .with("name", "variant")
+                        .with("value", "normal")
+                        .with("type", "enum")
+                        .with("values", &values.join(" ")),
+                )?;
+                self.send(&Message::new("wait"))
+            }
+            "set-param" => match (message.get("name"), message.get("value")) {
+                (Some("variant"), Some(value)) => {
+                    self.rules = variant_rules(value)
+                        .ok_or_else(|| format!("Unknown variant '{}'", value))?;
+                    Ok(())
+                }
+                (name, _) => Err(format!("Unknown parameter '{}'", name.unwrap_or(""))),
+            },
+            "init" => self.send(&Message::new("ready")),
+            "ping" => self.send(&Message::new("pong")),
+            "new-game" => {
+                self.worker.cancel();
+                self.thinking = None;
+                self.time_left = None;
+                Ok(())
+            }
+            "pos" => {
+                let board = match message.get("pos") {
+                    Some(text) => parse_position(text)?,
+                    None => Board::new(),
+                };
+                let mut game = Game::new(board.with_rules(self.rules));
+                for text in message.get("moves").unwrap_or("").split_whitespace() {
+                    let mv = parse_move(game.board(), text)?;
+                    game.play(&mv);
+                }
+                self.game = game;
+                Ok(())
+            }
+            "level" => {
+                self.level = parse_level(message)?;
+                Ok(())
+            }
+            "time" => {
+                if let Some(left) = message.get("left") {
+                    self.time_left = Some(parse_seconds(left)?);
+                }
+                Ok(())
+            }
+            "go" => self.go(message.has("ponder")),
+            "ponder-hit" => {
+                let deadline = self.budget().map(|budget| Instant::now() + budget);
+                if let Some(thinking) = &mut self.thinking {
+                    thinking.pondering = false;
+                    thinking.deadline = deadline;
+                }
+                Ok(())
+            }
+            "stop" => {
+                self.worker.stop();
+                if let Some(thinking) = &mut self.thinking {
+                    thinking.pondering = false;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn go(&mut self, ponder: bool) -> Result<(), String> {
+        if self.thinking.is_some() {
+            return Err(String::from("Already thinking"));
+        }
+        if self.game.board().legal_moves().is_empty() {
+            return Err(String::from("No legal moves"));
+        }
+
+        let now = Instant::now();
+        // pondering goes on until the opponent's move is known
+        let deadline = if ponder {
+            None
+        } else {
+            self.budget().map(|budget| now + budget)
+        };
+        self.worker
+            .set_max_depth(self.level.depth.unwrap_or(self.max_depth));
+        self.worker.start(&self.game)?;
+        self.thinking = Some(Thinking {
+            started: now,
+            deadline,
+            pondering: ponder,
+            result: None,
+        });
+        Ok(())
+    }
+
+    // the time for this move: what is left spread over the moves still to come
+    fn budget(&self) -> Option<Duration> {
+        let level = &self.level;
+        if level.infinite {
+            return None;
+        }
+        if let Some(move_time) = level.move_time {
+            return Some(move_time);
+        }
+
+        let left = self.time_left.or(level.time)?;
+        let moves_to_go = match level.moves {
+            0 => DEFAULT_MOVES_TO_GO,
+            moves => moves - (self.game.ply() as u32 / 2) % moves,
+        };
+        Some((left / moves_to_go + level.increment).min(left / 2))
+    }
+
+    // stops the search when its time is up and tells the move once it may
+    fn watch_thinking(&mut self) -> Result<(), String> {
+        let thinking = match &mut self.thinking {
+            Some(thinking) => thinking,
+            None => return Ok(()),
+        };
+
+        if thinking.result.is_none() {
+            match self.worker.poll() {
+                Ok(result) => thinking.result = result,
+                Err(err) => {
+                    self.thinking = None;
+                    return self.send(&Message::new("error").with("message", &err));
+                }
+            }
+        }
+        if thinking
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.worker.stop();
+        }
+        if thinking.pondering || (thinking.result.is_none() && self.worker.is_thinking()) {
+            return Ok(());
+        }
+
+        let thinking = self.thinking.take().unwrap();
+        let result = match thinking.result {
+            Some(result) => result,
+            None => return self.send(&Message::new("error").with("message", "No move was found")),
+        };
+        self.send(
+            &Message::new("info")
+                .with("depth", &result.depth.to_string())
+                .with(
+                    "score",
+                    &format!("{:.2}", result.score as f64 / MAN_VALUE as f64),
+                )
+                .with("nodes", &result.nodes.to_string())
+                .with(
+                    "time",
+                    &format!("{:.3}", thinking.started.elapsed().as_secs_f64()),
+                ),
+        )?;
+        self.send(&Message::new("done").with("move", &write_move(&result.best)))
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), String> {
+        writeln!(self.output, "{}", message)
+            .and_then(|_| self.output.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn parse_level(message: &Message) -> Result<Level, String> {
+    let mut level = Level {
+        infinite: message.has("infinite"),
+        ..Level::default()
+    };
+    if let Some(depth) = message.get("depth") {
+        level.depth = Some(
+            depth
+                .parse()
+                .map_err(|_| format!("Invalid depth '{}'", depth))?,
+        );
+    }
+    if let Some(moves) = message.get("moves") {
+        level.moves = moves
+            .parse()
+            .map_err(|_| format!("Invalid number of moves '{}'", moves))?;
+    }
+    level.move_time = message.get("move-time").map(parse_seconds).transpose()?;
+    level.time = message.get("time").map(parse_seconds).transpose()?;
+    if let Some(increment) = message.get("inc") {
+        level.increment = parse_seconds(increment)?;
+    }
+    Ok(level)
+}
+
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    text.parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds >= 0.0 && seconds.is_finite())
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| format!("Invalid time '{}'", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::rules::CaptureRule;
+    use std::io::{pipe, BufReader, Cursor, PipeWriter};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    // searches until it is told to stop, then plays the first legal move
+    struct Patient;
+
+    impl Engine for Patient {
+        fn search(
+            &mut self,
+            game: &Game,
+            stop: &AtomicBool,
+        ) -> Result<Option<SearchResult>, String> {
+            while !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Ok(game.board().legal_moves().first().map(|best| SearchResult {
+                best: best.clone(),
+                score: 0,
+                depth: 1,
+                nodes: 1,
+            }))
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        fn commands(&self) -> Vec<String> {
+            let text = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            text.lines()
+                .map(|line| Message::parse(line).unwrap().command)
+                .collect()
+        }
+
+        // waits for `command` to have been sent `times` times, giving up after a while
+        fn wait_for(&self, command: &str, times: usize) -> bool {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while Instant::now() < deadline {
+                if self
+                    .commands()
+                    .iter()
+                    .filter(|sent| *sent == command)
+                    .count()
+                    >= times
+                {
+                    return true;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            false
+        }
+    }
+
+    struct Session {
+        input: PipeWriter,
+        output: Output,
+        server: thread::JoinHandle<Result<(), String>>,
+    }
+
+    impl Session {
+        fn start() -> Session {
+            let (reader, input) = pipe().unwrap();
+            let output = Output::default();
+            let server = HubServer::new(Worker::new(Patient), output.clone());
+            let server = thread::spawn(move || server.run(BufReader::new(reader)));
+            Session {
+                input,
+                output,
+                server,
+            }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.input, "{}", line).unwrap();
+        }
+
+        fn quit(mut self) {
+            self.send("quit");
+            self.server.join().unwrap().unwrap();
+        }
+    }
+
+    fn server() -> HubServer<Vec<u8>> {
+        HubServer::new(Worker::new(Patient), vec![])
+    }
+
+    fn handle(server: &mut HubServer<Vec<u8>>, line: &str) -> Result<(), String> {
+        server.handle(&Message::parse(line).unwrap())
+    }
+
+    #[test]
+    fn answers_the_handshake() {
+        let output = Output::default();
+        HubServer::new(Worker::new(Patient), output.clone())
+            .with_name("test")
+            .run(Cursor::new("hub\ninit\nping\nquit\n"))
+            .unwrap();
+        assert_eq!(output.commands(), ["id", "param", "wait", "ready", "pong"]);
+
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let param = Message::parse(text.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(param.get("name"), Some("variant"));
+        assert_eq!(param.get("values"), Some("normal majority"));
+    }
+
+    #[test]
+    fn sets_the_variant() {
+        let mut server = server();
+        handle(&mut server, "set-param name=variant value=majority").unwrap();
+        handle(&mut server, "pos").unwrap();
+        assert_eq!(server.game.board().rules().capture, CaptureRule::Majority);
+
+        assert!(handle(&mut server, "set-param name=variant value=losing").is_err());
+        assert!(handle(&mut server, "set-param name=book value=none").is_err());
+    }
+
+    #[test]
+    fn spreads_the_time_over_the_moves() {
+        let mut server = server();
+        assert_eq!(server.budget(), None);
+
+        handle(&mut server, "level move-time=0.5").unwrap();
+        assert_eq!(server.budget(), Some(Duration::from_millis(500)));
+
+        // 40 moves in a minute, 10 of them played
+        handle(&mut server, "level moves=40 time=60").unwrap();
+        assert_eq!(server.budget(), Some(Duration::from_millis(1500)));
+        for _ in 0..20 {
+            let mv = server.game.board().legal_moves()[0].clone();
+            server.game.play(&mv);
+        }
+        assert_eq!(server.game.ply(), 20);
+        assert_eq!(server.budget(), Some(Duration::from_secs(2)));
+
+        // the whole game in a minute with an increment, then what the GUI says is left
+        handle(&mut server, "level time=60 inc=1").unwrap();
+        assert_eq!(server.budget(), Some(Duration::from_secs(3)));
+        handle(&mut server, "time left=3").unwrap();
+        assert_eq!(server.budget(), Some(Duration::from_millis(1100)));
+        handle(&mut server, "time left=1").unwrap();
+        assert_eq!(server.budget(), Some(Duration::from_millis(500)));
+
+        handle(&mut server, "level infinite").unwrap();
+        assert_eq!(server.budget(), None);
+    }
+
+    #[test]
+    fn keeps_to_the_move_time() {
+        let mut session = Session::start();
+        let started = Instant::now();
+        session.send("level move-time=0.2");
+        session.send("go");
+        assert!(session.output.wait_for("done", 1));
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(session.output.commands(), ["info", "done"]);
+        session.quit();
+    }
+
+    #[test]
+    fn tells_a_pondered_move_only_after_the_opponents() {
+        let mut session = Session::start();
+        session.send("level move-time=0.05");
+        session.send("go ponder");
+        thread::sleep(Duration::from_millis(300));
+        assert!(session.output.commands().is_empty());
+        session.send("ponder-hit");
+        assert!(session.output.wait_for("done", 1));
+
+        session.send("go ponder");
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(session.output.commands(), ["info", "done"]);
+        session.send("stop");
+        assert!(session.output.wait_for("done", 2));
+        assert_eq!(session.output.commands().len(), 4);
+        session.quit();
+    }
+
+    #[test]
+    fn a_new_game_cancels_the_search() {
+        let mut session = Session::start();
+        session.send("level infinite");
+        session.send("go");
+        session.send("new-game");
+        thread::sleep(Duration::from_millis(100));
+        session.send("go");
+        session.send("stop");
+        assert!(session.output.wait_for("done", 1));
+        thread::sleep(Duration::from_millis(100));
+        // one move for the second search, and no complaint that it was already thinking
+        assert_eq!(session.output.commands(), ["info", "done"]);
+        session.quit();
+    }
+}
@@ -0,0 +1,7 @@
+pub mod dxp;
+pub mod engine;
+pub mod hub;
+pub mod net;
+pub mod notation;
+pub mod rules;
+pub mod tablebase;
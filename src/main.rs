@@ -5,35 +5,249 @@ mod game_states;
 mod asset_loader;
 mod game_events;
 mod game_machine;
+mod options;
 
 use crate::asset_loader::Assets;
 use crate::game_machine::context::Context;
-use crate::game_states::PauseState;
+use crate::game_states::{PauseState, ReviewState, SpectatorState};
+use crate::options::{EngineStyle, Options};
+use checkers::dxp::{DxpGame, GameSettings};
+use checkers::engine::{Book, Mcts, Searcher, Worker};
+use checkers::hub::{HubEngine, HubServer};
+use checkers::net::{Guest, Host, Link, Spectator};
+use checkers::notation::{parse_fen, write_move, PdnGame};
+use checkers::rules::{Board, Game, Outcome, Rules, Side};
+use checkers::tablebase::Tablebase;
 use game_machine::context::DefaultContext;
 use game_machine::runtime::Runtime;
 use game_states::BoardState;
+use game_states::Player;
 use game_states::WinState;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
 
 fn main() -> Result<(), String> {
+    let options = Options::parse(std::env::args().skip(1))?;
+
+    let game = match (&options.load, &options.fen) {
+        (Some(path), _) => {
+            let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+            PdnGame::parse(&text)?.to_game_with_rules(options.rules)?
+        }
+        (None, Some(fen)) => Game::new(parse_fen(fen)?.with_rules(options.rules)),
+        (None, None) => Game::new(Board::new().with_rules(options.rules)),
+    };
+
+    let tablebase = match &options.tablebase {
+        Some(path) => Some(Arc::new(Tablebase::load(path)?)),
+        None => None,
+    };
+    let book = match &options.book {
+        Some(path) => Some(Arc::new(Book::load(path)?)),
+        None => None,
+    };
+
+    if options.hub_mode {
+        return serve_hub(&options, &tablebase, book);
+    }
+    if options.dxp_connect.is_some() || options.dxp_listen.is_some() {
+        return play_dxp(&options, &tablebase, book, &game);
+    }
+
+    // a guest plays the host's game, whatever was loaded here
+    let (game, link, session): (Game, Option<Box<dyn Link>>, _) =
+        match (options.host, &options.join) {
+            (Some(port), _) => {
+                let host = Host::bind(("0.0.0.0", port), options.side, game.clone())?
+                    .with_name(&options.name);
+                (game, Some(Box::new(host)), None)
+            }
+            (None, Some(address)) => {
+                let guest = match options.session {
+                    Some(session) => Guest::resume(address.as_str(), &options.name, session)?,
+                    None => Guest::join(address.as_str(), &options.name)?,
+                };
+                let session = guest.session();
+                (guest.game().clone(), Some(Box::new(guest)), Some(session))
+            }
+            (None, None) => (game, None, None),
+        };
+    let spectator = match &options.watch {
+        Some(address) => Some(Spectator::watch(address.as_str(), &options.name)?),
+        None => None,
+    };
+
     let sdl_cxt = sdl2::init()?;
     let sdl_event = sdl_cxt.event()?;
     let ttf = sdl2::ttf::init().map_err(|e| e.to_string())?;
 
-    sdl_event.register_custom_event::<game_events::WinColorEvent>()?;
+    sdl_event.register_custom_event::<game_events::GameOverEvent>()?;
+    sdl_event.register_custom_event::<game_events::ReviewEvent>()?;
 
     let assets = Assets::new(&ttf)?;
     let mut runtime = Runtime::new(&assets, &sdl_event);
     let mut context = DefaultContext::new(&sdl_cxt)?;
 
     let text_creator = context.canvas().texture_creator();
+    let clipboard = sdl_cxt.video()?.clipboard();
 
-    let mut board_state = BoardState::new(&text_creator);
+    let rules = game.board().rules();
+    let mut board_state = BoardState::new(&text_creator, game, clipboard);
+    if let Some(link) = link {
+        board_state = board_state
+            .with_link(link)
+            .with_chat(sdl_cxt.video()?.text_input());
+    }
+    // the way back in should the game have to be restarted
+    if let Some(session) = session {
+        board_state = board_state.with_session(session);
+    }
+    for side in &options.computer {
+        let mut worker = computer(&options, &tablebase, rules, Some(options.move_time))?;
+        if let Some(book) = &book {
+            worker = worker.with_book(book.clone());
+        }
+        board_state = board_state.with_player(*side, Player::Computer(worker));
+    }
+    if let Some(book) = book {
+        board_state = board_state.with_book(book);
+    }
+    // an external engine is not started a second time just for hints
+    let hinter = match options.engine {
+        EngineStyle::Hub => Worker::new(searcher(&options, &tablebase, Some(options.move_time))),
+        _ => computer(&options, &tablebase, rules, Some(options.move_time))?,
+    };
+    board_state = board_state.with_hinter(hinter);
     let mut win_state = WinState::new(&text_creator);
     let mut pause_state = PauseState::new(&text_creator);
+    let mut review_state = ReviewState::new(&text_creator);
+    if let Some(tablebase) = &tablebase {
+        review_state = review_state.with_tablebase(tablebase.clone());
+    }
 
-    runtime.add_state(&mut board_state);
+    // a spectator only gets to see the game, in place of the board that is played on
+    let mut spectator_state =
+        spectator.map(|spectator| SpectatorState::new(&text_creator, spectator));
+    match &mut spectator_state {
+        Some(spectator_state) => runtime.add_state(spectator_state),
+        None => runtime.add_state(&mut board_state),
+    }
     runtime.add_state(&mut win_state);
     runtime.add_state(&mut pause_state);
+    runtime.add_state(&mut review_state);
 
     runtime.run(&mut context)
 }
+
+fn computer(
+    options: &Options,
+    tablebase: &Option<Arc<Tablebase>>,
+    rules: Rules,
+    time_limit: Option<Duration>,
+) -> Result<Worker, String> {
+    let worker = match options.engine {
+        EngineStyle::AlphaBeta => Worker::new(searcher(options, tablebase, time_limit)),
+        EngineStyle::Mcts => {
+            let mut mcts = Mcts::new(options.iterations);
+            if let Some(time_limit) = time_limit {
+                mcts = mcts.with_time_limit(time_limit);
+            }
+            if let Some(seed) = options.seed {
+                mcts = mcts.with_seed(seed);
+            }
+            Worker::new(mcts)
+        }
+        EngineStyle::Hub => {
+            let program = options.hub.as_ref().unwrap();
+            let mut engine = HubEngine::launch(program, rules)?.with_depth(options.depth);
+            if let Some(time_limit) = time_limit {
+                engine = engine.with_move_time(time_limit);
+            }
+            Worker::new(engine)
+        }
+    };
+    Ok(worker)
+}
+
+fn searcher(
+    options: &Options,
+    tablebase: &Option<Arc<Tablebase>>,
+    time_limit: Option<Duration>,
+) -> Searcher {
+    let mut searcher = Searcher::new(options.depth);
+    if let Some(time_limit) = time_limit {
+        searcher = searcher.with_time_limit(time_limit);
+    }
+    if let Some(tablebase) = tablebase {
+        searcher = searcher.with_tablebase(tablebase.clone());
+    }
+    searcher
+}
+
+// the GUI on the other end keeps the time, so the engine gets no time limit of its own
+fn serve_hub(
+    options: &Options,
+    tablebase: &Option<Arc<Tablebase>>,
+    book: Option<Arc<Book>>,
+) -> Result<(), String> {
+    let mut worker = computer(options, tablebase, Rules::default(), None)?;
+    if let Some(book) = book {
+        worker = worker.with_book(book);
+    }
+
+    HubServer::new(worker, std::io::stdout())
+        .with_max_depth(options.depth)
+        .run(BufReader::new(std::io::stdin()))
+}
+
+// the game's clock is kept by `DxpGame`, which stops the engine once its time is up
+fn play_dxp(
+    options: &Options,
+    tablebase: &Option<Arc<Tablebase>>,
+    book: Option<Arc<Book>>,
+    game: &Game,
+) -> Result<(), String> {
+    let mut worker = computer(options, tablebase, Rules::default(), None)?;
+    if let Some(book) = book {
+        worker = worker.with_book(book);
+    }
+
+    let name = env!("CARGO_PKG_NAME");
+    let mut dxp = match (&options.dxp_connect, options.dxp_listen) {
+        (Some(address), _) => {
+            let stream = TcpStream::connect(address).map_err(|e| e.to_string())?;
+            let settings = GameSettings {
+                side: options.computer.first().copied().unwrap_or(Side::Green),
+                start: if game.ply() == 0 && *game.board() == Board::new() {
+                    None
+                } else {
+                    Some(*game.board())
+                },
+                ..GameSettings::default()
+            };
+            DxpGame::initiate(stream, name, &settings)?
+        }
+        (None, Some(port)) => {
+            let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| e.to_string())?;
+            println!("Waiting for a game on port {}", port);
+            let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+            DxpGame::follow(stream, name)?
+        }
+        (None, None) => return Ok(()),
+    };
+    println!("Playing {:?} against {}", dxp.side(), dxp.peer_name());
+
+    while dxp.play_ply(&mut worker)? {
+        if let Some(mv) = dxp.game().moves().last() {
+            println!("{}. {}", dxp.game().ply(), write_move(mv));
+        }
+    }
+    match dxp.outcome() {
+        Some(Outcome::Win(side)) => println!("{:?} wins", side),
+        Some(Outcome::Draw(reason)) => println!("Draw by {:?}", reason),
+        None => println!("The game was stopped"),
+    }
+    Ok(())
+}
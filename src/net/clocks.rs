@@ -0,0 +1,54 @@
+use crate::rules::Side;
+use std::time::{Duration, Instant};
+
+/// The time each side has spent on its moves. Only the side to move has its clock running, and
+/// neither does while the game waits for a player.
+#[derive(Debug, Clone, Copy)]
+pub struct Clocks {
+    green: Duration,
+    red: Duration,
+    running: Option<(Side, Instant)>,
+}
+
+impl Clocks {
+    pub fn new(green: Duration, red: Duration) -> Clocks {
+        Clocks {
+            green,
+            red,
+            running: None,
+        }
+    }
+
+    /// The time used by `side`, the move it is thinking about included.
+    pub fn used(&self, side: Side) -> Duration {
+        let used = match side {
+            Side::Green => self.green,
+            Side::Red => self.red,
+        };
+        match self.running {
+            Some((running, since)) if running == side => used + since.elapsed(),
+            _ => used,
+        }
+    }
+
+    /// Stops the clock that runs and starts the one of `side`.
+    pub fn start(&mut self, side: Side) {
+        self.stop();
+        self.running = Some((side, Instant::now()));
+    }
+
+    pub fn stop(&mut self) {
+        if let Some((side, since)) = self.running.take() {
+            match side {
+                Side::Green => self.green += since.elapsed(),
+                Side::Red => self.red += since.elapsed(),
+            }
+        }
+    }
+}
+
+impl Default for Clocks {
+    fn default() -> Clocks {
+        Clocks::new(Duration::ZERO, Duration::ZERO)
+    }
+}
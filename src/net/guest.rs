@@ -0,0 +1,274 @@
+use crate::net::{replay, Clocks, Link, Message, NetEvent, Peer, VERSION};
+use crate::notation::{parse_move, write_move};
+use crate::rules::{Game, Move, Side};
+use std::collections::VecDeque;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// how long the host may take to answer the hello
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+// how long to keep trying to get back into the game once the connection dropped
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The side of a network game that joins a host. It plays only the moves the host sends back,
+/// its own included. When the connection drops it tries to resume the session in the background.
+pub struct Guest {
+    peer: Peer,
+    address: SocketAddr,
+    side: Side,
+    name: String,
+    session: u64,
+    game: Game,
+    clocks: Clocks,
+    is_connected: bool,
+    reconnect_timeout: Duration,
+    // the attempt to get back in, while the connection is lost
+    reconnecting: Option<JoinHandle<Result<Seat, String>>>,
+    events: VecDeque<NetEvent>,
+}
+
+// what the host hands a player that is let in
+struct Seat {
+    peer: Peer,
+    side: Side,
+    session: u64,
+    game: Game,
+    clocks: Clocks,
+}
+
+impl Guest {
+    /// Joins the game hosted at `address`, taking whichever side the host left free.
+    pub fn join<A: ToSocketAddrs>(address: A, name: &str) -> Result<Guest, String> {
+        let hello = Message::Hello {
+            version: VERSION,
+            name: String::from(name),
+        };
+        Guest::enter(address, name, &hello)
+    }
+
+    /// Goes back to the game of `session` at `address`, the one left when the connection dropped.
+    pub fn resume<A: ToSocketAddrs>(address: A, name: &str, session: u64) -> Result<Guest, String> {
+        let resume = Message::Resume {
+            version: VERSION,
+            session,
+            name: String::from(name),
+        };
+        Guest::enter(address, name, &resume)
+    }
+
+    fn enter<A: ToSocketAddrs>(
+        address: A,
+        name: &str,
+        greeting: &Message,
+    ) -> Result<Guest, String> {
+        let address = address
+            .to_socket_addrs()
+            .map_err(|e| e.to_string())?
+            .next()
+            .ok_or_else(|| String::from("The address could not be resolved"))?;
+        let seat = take_seat(address, greeting, JOIN_TIMEOUT)?;
+        Ok(Guest {
+            peer: seat.peer,
+            address,
+            side: seat.side,
+            name: String::from(name),
+            session: seat.session,
+            game: seat.game,
+            clocks: seat.clocks,
+            is_connected: true,
+            reconnect_timeout: RECONNECT_TIMEOUT,
+            reconnecting: None,
+            events: VecDeque::new(),
+        })
+    }
+
+    /// How long to keep trying to resume the game once the connection dropped.
+    pub fn with_reconnect_timeout(mut self, timeout: Duration) -> Guest {
+        self.reconnect_timeout = timeout;
+        self
+    }
+
+    /// The game as it stood when joining, with every move since.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The id to resume the game with.
+    pub fn session(&self) -> u64 {
+        self.session
+    }
+
+    fn listen(&mut self) {
+        match self.peer.try_receive() {
+            Ok(Some(Message::Move(text))) => match parse_move(self.game.board(), &text) {
+                Ok(mv) => self.play(mv),
+                Err(e) => self.fail(format!("The host played badly: {}", e)),
+            },
+            Ok(Some(Message::Refused(reason))) => self.events.push_back(NetEvent::Refused(reason)),
+            Ok(Some(Message::Chat { name, text })) => {
+                self.events.push_back(NetEvent::Chat { name, text })
+            }
+            Ok(_) => {}
+            Err(_) => {
+                self.is_connected = false;
+                self.clocks.stop();
+                self.reconnecting = Some(self.reconnect());
+                self.events.push_back(NetEvent::Lost);
+            }
+        }
+    }
+
+    // a host that can not be trusted with the game any more is left for good
+    fn fail(&mut self, reason: String) {
+        self.peer.close();
+        self.is_connected = false;
+        self.clocks.stop();
+        self.game.forfeit(self.side.opponent());
+        self.events.push_back(NetEvent::Failed(reason));
+    }
+
+    fn play(&mut self, mv: Move) {
+        self.game.play(&mv);
+        if self.game.is_over() {
+            self.clocks.stop();
+        } else {
+            self.clocks.start(self.game.board().turn());
+        }
+        self.events.push_back(NetEvent::Moved(mv));
+    }
+
+    // keeps asking the host for the seat back until it answers or the time is up
+    fn reconnect(&self) -> JoinHandle<Result<Seat, String>> {
+        let address = self.address;
+        let deadline = Instant::now() + self.reconnect_timeout;
+        let resume = Message::Resume {
+            version: VERSION,
+            session: self.session,
+            name: self.name.clone(),
+        };
+        thread::spawn(move || loop {
+            match take_seat(address, &resume, JOIN_TIMEOUT) {
+                Ok(seat) => return Ok(seat),
+                Err(e) if Instant::now() >= deadline => return Err(e),
+                Err(_) => thread::sleep(RETRY_INTERVAL),
+            }
+        })
+    }
+
+    fn look_at_reconnecting(&mut self) {
+        let reconnecting = match self.reconnecting.take() {
+            Some(reconnecting) if reconnecting.is_finished() => reconnecting,
+            reconnecting => {
+                self.reconnecting = reconnecting;
+                return;
+            }
+        };
+
+        match reconnecting.join() {
+            Ok(Ok(seat)) => {
+                // the host's game only grew meanwhile, the moves missed are played as they come
+                let played = self.game.moves().count();
+                if seat.side != self.side || seat.game.moves().count() < played {
+                    self.fail(String::from("The host's game is not the one left"));
+                    return;
+                }
+                self.peer = seat.peer;
+                self.is_connected = true;
+                for mv in seat.game.moves().skip(played) {
+                    self.game.play(mv);
+                    self.events.push_back(NetEvent::Moved(mv.clone()));
+                }
+                self.clocks = seat.clocks;
+                self.events.push_back(NetEvent::Resumed);
+            }
+            // the host stayed away past the timeout, the game is left unfinished as the host
+            // may well see it ending the other way
+            _ => self.events.push_back(NetEvent::Left),
+        }
+    }
+}
+
+impl Link for Guest {
+    fn side(&self) -> Side {
+        self.side
+    }
+
+    fn is_host(&self) -> bool {
+        false
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn send_move(&mut self, mv: &Move) -> Result<(), String> {
+        if self.game.board().turn() != self.side {
+            return Err(String::from("It is not your turn"));
+        }
+        self.peer.send(&Message::Move(write_move(mv)))
+    }
+
+    fn send_chat(&mut self, text: &str) -> Result<(), String> {
+        self.peer.send(&Message::Chat {
+            name: self.name.clone(),
+            text: String::from(text),
+        })
+    }
+
+    fn clocks(&self) -> Clocks {
+        self.clocks
+    }
+
+    fn poll(&mut self) -> Result<Option<NetEvent>, String> {
+        if self.events.is_empty() {
+            if self.is_connected {
+                self.listen();
+            } else {
+                self.look_at_reconnecting();
+            }
+        }
+        Ok(self.events.pop_front())
+    }
+}
+
+// greets the host and waits for the game it lets the player into
+fn take_seat(address: SocketAddr, greeting: &Message, timeout: Duration) -> Result<Seat, String> {
+    let stream = TcpStream::connect(address).map_err(|e| e.to_string())?;
+    let mut peer = Peer::new(stream)?;
+    peer.send(greeting)?;
+
+    match peer.receive(timeout)? {
+        Some(Message::Welcome {
+            version,
+            side: Some(side),
+            session: Some(session),
+            green_time,
+            red_time,
+            start,
+            moves,
+            ..
+        }) if version == VERSION => {
+            let game = replay(start, &moves)?;
+            let mut clocks = Clocks::new(green_time, red_time);
+            if !game.is_over() {
+                clocks.start(game.board().turn());
+            }
+            Ok(Seat {
+                peer,
+                side,
+                session,
+                game,
+                clocks,
+            })
+        }
+        Some(Message::Welcome { version, .. }) if version != VERSION => Err(format!(
+            "The host speaks version {} of the protocol",
+            version
+        )),
+        Some(Message::Refused(reason)) => Err(reason),
+        Some(message) => Err(format!("Unexpected answer '{}'", message)),
+        None => Err(String::from("The host did not answer")),
+    }
+}
@@ -0,0 +1,334 @@
+use crate::engine::Random;
+use crate::net::{Clocks, Link, Message, NetEvent, Peer, VERSION};
+use crate::notation::{parse_move, write_move};
+use crate::rules::{Game, Move, Side};
+use std::collections::VecDeque;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+// how long the seat of a guest whose connection dropped is kept
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The side of a network game that others join. It keeps the game that counts and checks
+/// every move against it before anyone gets to play it. Spectators hear of every move played.
+/// A guest that loses its connection may resume the session until the reconnect timeout.
+pub struct Host {
+    listener: TcpListener,
+    side: Side,
+    name: String,
+    session: u64,
+    game: Game,
+    clocks: Clocks,
+    reconnect_timeout: Duration,
+    // when the guest's connection dropped, while its seat is kept
+    lost_at: Option<Instant>,
+    // connections that have not said hello yet
+    joining: Vec<Peer>,
+    guest: Option<Peer>,
+    guest_name: String,
+    spectators: Vec<Peer>,
+    events: VecDeque<NetEvent>,
+}
+
+impl Host {
+    /// Waits for a guest on `address` to play `game` against, with the host playing `side`.
+    pub fn bind<A: ToSocketAddrs>(address: A, side: Side, game: Game) -> Result<Host, String> {
+        let listener = TcpListener::bind(address).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        Ok(Host {
+            listener,
+            side,
+            name: String::from("Host"),
+            session: Random::from_time().next_u64(),
+            game,
+            clocks: Clocks::default(),
+            reconnect_timeout: RECONNECT_TIMEOUT,
+            lost_at: None,
+            joining: vec![],
+            guest: None,
+            guest_name: String::new(),
+            spectators: vec![],
+            events: VecDeque::new(),
+        })
+    }
+
+    /// The name the host's chat lines go by.
+    pub fn with_name(mut self, name: &str) -> Host {
+        self.name = String::from(name);
+        self
+    }
+
+    /// How long a guest that lost its connection may take to resume the game.
+    pub fn with_reconnect_timeout(mut self, timeout: Duration) -> Host {
+        self.reconnect_timeout = timeout;
+        self
+    }
+
+    /// The id a guest resumes the game with.
+    pub fn session(&self) -> u64 {
+        self.session
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener.local_addr().map_err(|e| e.to_string())
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    // a connection that can not be set up is dropped, the listener is tried again next time
+    fn accept(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(false).is_err() {
+                continue;
+            }
+            if let Ok(peer) = Peer::new(stream) {
+                self.joining.push(peer);
+            }
+        }
+    }
+
+    // answers whoever connected, the first to ask to play with the right version gets to
+    fn greet(&mut self) {
+        let mut index = 0;
+        while index < self.joining.len() {
+            let message = match self.joining[index].try_receive() {
+                Ok(Some(message)) => message,
+                Ok(None) => {
+                    index += 1;
+                    continue;
+                }
+                Err(_) => {
+                    self.joining.remove(index);
+                    continue;
+                }
+            };
+
+            let mut peer = self.joining.remove(index);
+            let (version, name, is_watching) = match message {
+                Message::Hello { version, name } => (version, name, false),
+                Message::Watch { version, name } => (version, name, true),
+                Message::Resume {
+                    version, session, ..
+                } => {
+                    self.resume(peer, version, session);
+                    continue;
+                }
+                _ => continue,
+            };
+            let refusal = if version != VERSION {
+                Some(format!(
+                    "The host speaks version {} of the protocol",
+                    VERSION
+                ))
+            } else if !is_watching && self.guest.is_some() {
+                Some(String::from("The game already has two players"))
+            } else if !is_watching && self.lost_at.is_some() {
+                Some(String::from(
+                    "The game is waiting for its player to reconnect",
+                ))
+            } else if !is_watching && self.game.is_over() {
+                Some(String::from("The game is over"))
+            } else {
+                None
+            };
+            if let Some(reason) = refusal {
+                let _ = peer.send(&Message::Refused(reason));
+                continue;
+            }
+
+            let side = if is_watching {
+                None
+            } else {
+                Some(self.side.opponent())
+            };
+            if peer.send(&self.welcome(side)).is_err() {
+                continue;
+            }
+            if is_watching {
+                self.spectators.push(peer);
+            } else {
+                self.guest = Some(peer);
+                self.guest_name = name.clone();
+                self.start_clock();
+                self.events.push_back(NetEvent::Joined(name));
+            }
+        }
+    }
+
+    // the guest takes its seat back, under the name it first joined with
+    fn resume(&mut self, mut peer: Peer, version: u32, session: u64) {
+        let refusal = if version != VERSION {
+            Some(format!(
+                "The host speaks version {} of the protocol",
+                VERSION
+            ))
+        } else if session != self.session {
+            Some(String::from("There is no such session"))
+        } else if self.lost_at.is_none() {
+            Some(String::from("The session has no player to resume"))
+        } else {
+            None
+        };
+        if let Some(reason) = refusal {
+            let _ = peer.send(&Message::Refused(reason));
+            return;
+        }
+
+        if peer.send(&self.welcome(Some(self.side.opponent()))).is_ok() {
+            self.guest = Some(peer);
+            self.lost_at = None;
+            self.start_clock();
+            self.events.push_back(NetEvent::Resumed);
+        }
+    }
+
+    // the game so far, only a player gets to know the session
+    fn welcome(&self, side: Option<Side>) -> Message {
+        Message::Welcome {
+            version: VERSION,
+            side,
+            session: side.map(|_| self.session),
+            green_time: self.clocks.used(Side::Green),
+            red_time: self.clocks.used(Side::Red),
+            rules: self.game.start().rules(),
+            start: *self.game.start(),
+            moves: self.game.moves().map(write_move).collect(),
+        }
+    }
+
+    fn start_clock(&mut self) {
+        if self.game.is_over() {
+            self.clocks.stop();
+        } else {
+            self.clocks.start(self.game.board().turn());
+        }
+    }
+
+    fn listen_to_guest(&mut self) {
+        let guest = match &mut self.guest {
+            Some(guest) => guest,
+            None => return,
+        };
+
+        match guest.try_receive() {
+            Ok(Some(Message::Move(text))) => {
+                match check_move(&self.game, self.side.opponent(), &text) {
+                    Ok(mv) => self.play(mv),
+                    Err(reason) => {
+                        let _ = guest.send(&Message::Refused(reason));
+                    }
+                }
+            }
+            // the guest's own name is used, whatever it says
+            Ok(Some(Message::Chat { text, .. })) => self.say(self.guest_name.clone(), text),
+            Ok(_) => {}
+            Err(_) => self.drop_guest(),
+        }
+    }
+
+    // the move counts from here on, the guest and the spectators hear of it as well
+    fn play(&mut self, mv: Move) {
+        self.game.play(&mv);
+        self.start_clock();
+        let message = Message::Move(write_move(&mv));
+        self.events.push_back(NetEvent::Moved(mv));
+        self.broadcast(&message);
+    }
+
+    fn say(&mut self, name: String, text: String) {
+        let message = Message::Chat {
+            name: name.clone(),
+            text: text.clone(),
+        };
+        self.events.push_back(NetEvent::Chat { name, text });
+        self.broadcast(&message);
+    }
+
+    fn broadcast(&mut self, message: &Message) {
+        if let Some(guest) = &mut self.guest {
+            if guest.send(message).is_err() {
+                self.drop_guest();
+            }
+        }
+        // spectators that went away are only noticed here
+        self.spectators
+            .retain_mut(|spectator| spectator.send(message).is_ok());
+    }
+
+    // the seat is kept for a while, with the clocks stopped
+    fn drop_guest(&mut self) {
+        self.guest = None;
+        self.lost_at = Some(Instant::now());
+        self.clocks.stop();
+        self.events.push_back(NetEvent::Lost);
+    }
+
+    // a guest that stays away too long loses the game, so that nobody else can take its seat
+    fn give_up_on_guest(&mut self) {
+        if let Some(lost_at) = self.lost_at {
+            if lost_at.elapsed() >= self.reconnect_timeout {
+                let absent = self.side.opponent();
+                self.lost_at = None;
+                self.game.forfeit(absent);
+                self.clocks.stop();
+                self.events.push_back(NetEvent::Left);
+                self.broadcast(&Message::Forfeit(absent));
+            }
+        }
+    }
+}
+
+impl Link for Host {
+    fn side(&self) -> Side {
+        self.side
+    }
+
+    fn is_host(&self) -> bool {
+        true
+    }
+
+    fn is_connected(&self) -> bool {
+        self.guest.is_some()
+    }
+
+    fn send_move(&mut self, mv: &Move) -> Result<(), String> {
+        let mv = check_move(&self.game, self.side, &write_move(mv))?;
+        self.play(mv);
+        Ok(())
+    }
+
+    fn send_chat(&mut self, text: &str) -> Result<(), String> {
+        self.say(self.name.clone(), String::from(text));
+        Ok(())
+    }
+
+    fn clocks(&self) -> Clocks {
+        self.clocks
+    }
+
+    fn poll(&mut self) -> Result<Option<NetEvent>, String> {
+        if self.events.is_empty() {
+            self.accept();
+            self.greet();
+            self.listen_to_guest();
+            self.give_up_on_guest();
+        }
+        Ok(self.events.pop_front())
+    }
+}
+
+fn check_move(game: &Game, side: Side, text: &str) -> Result<Move, String> {
+    if game.is_over() {
+        return Err(String::from("The game is over"));
+    }
+    if game.board().turn() != side {
+        return Err(String::from("It is not your turn"));
+    }
+    parse_move(game.board(), text)
+}
@@ -0,0 +1,201 @@
+use crate::notation::{parse_fen, write_fen};
+use crate::rules::{variant_name, variant_rules, Board, Rules, Side};
+use std::fmt;
+use std::time::Duration;
+
+pub const VERSION: u32 = 2;
+
+/// The messages of a network game, one per line: a lowercase word for the kind followed by its
+/// fields separated by spaces. Moves are written in numeric notation, positions as FEN, session
+/// ids in hexadecimal and times in milliseconds.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Message {
+    /// The first message of whoever joins to play.
+    Hello { version: u32, name: String },
+    /// The first message of whoever joins to watch.
+    Watch { version: u32, name: String },
+    /// The first message of a player coming back to the game of `session`.
+    Resume {
+        version: u32,
+        session: u64,
+        name: String,
+    },
+    /// The host's answer: the side of the guest, `None` for a spectator, and the game so far.
+    /// Only the player is told the session, with the time each side has used up to now.
+    Welcome {
+        version: u32,
+        side: Option<Side>,
+        session: Option<u64>,
+        green_time: Duration,
+        red_time: Duration,
+        rules: Rules,
+        start: Board,
+        moves: Vec<String>,
+    },
+    /// A move asked for by the guest, or one the host has played or accepted.
+    Move(String),
+    /// Why the host would not take a move or let someone join.
+    Refused(String),
+    /// A line said by a player, passed on by the host to everyone else.
+    Chat { name: String, text: String },
+    /// The side that gave up the game, by staying away past the reconnect timeout.
+    Forfeit(Side),
+}
+
+impl Message {
+    pub fn parse(line: &str) -> Result<Message, String> {
+        let (kind, rest) = split_word(line.trim());
+        match kind {
+            "hello" => {
+                let (version, name) = split_word(rest);
+                Ok(Message::Hello {
+                    version: parse_version(version)?,
+                    name: String::from(name),
+                })
+            }
+            "watch" => {
+                let (version, name) = split_word(rest);
+                Ok(Message::Watch {
+                    version: parse_version(version)?,
+                    name: String::from(name),
+                })
+            }
+            "resume" => {
+                let (version, rest) = split_word(rest);
+                let (session, name) = split_word(rest);
+                Ok(Message::Resume {
+                    version: parse_version(version)?,
+                    session: parse_session(session)?,
+                    name: String::from(name),
+                })
+            }
+            "welcome" => {
+                let mut words = rest.split_whitespace();
+                let mut next = || {
+                    words
+                        .next()
+                        .ok_or_else(|| format!("Incomplete message '{}'", line))
+                };
+                let version = parse_version(next()?)?;
+                let side = match next()? {
+                    "green" => Some(Side::Green),
+                    "red" => Some(Side::Red),
+                    "none" => None,
+                    side => return Err(format!("Unknown side '{}'", side)),
+                };
+                let session = match next()? {
+                    "none" => None,
+                    session => Some(parse_session(session)?),
+                };
+                let green_time = parse_time(next()?)?;
+                let red_time = parse_time(next()?)?;
+                let variant = next()?;
+                let rules = variant_rules(variant)
+                    .ok_or_else(|| format!("Unknown variant '{}'", variant))?;
+                let start = parse_fen(next()?)?.with_rules(rules);
+                Ok(Message::Welcome {
+                    version,
+                    side,
+                    session,
+                    green_time,
+                    red_time,
+                    rules,
+                    start,
+                    moves: words.map(String::from).collect(),
+                })
+            }
+            "move" if !rest.is_empty() => Ok(Message::Move(String::from(rest))),
+            "refused" => Ok(Message::Refused(String::from(rest))),
+            "forfeit" => match rest {
+                "green" => Ok(Message::Forfeit(Side::Green)),
+                "red" => Ok(Message::Forfeit(Side::Red)),
+                side => Err(format!("Unknown side '{}'", side)),
+            },
+            "chat" => {
+                let (name, text) = rest
+                    .split_once(": ")
+                    .ok_or_else(|| format!("Chat without a name '{}'", line))?;
+                Ok(Message::Chat {
+                    name: String::from(name),
+                    text: String::from(text),
+                })
+            }
+            _ => Err(format!("Unknown message '{}'", line)),
+        }
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let (word, rest) = text.split_at(end);
+    (word, rest.trim_start())
+}
+
+fn parse_version(text: &str) -> Result<u32, String> {
+    text.parse()
+        .map_err(|_| format!("Invalid protocol version '{}'", text))
+}
+
+fn parse_session(text: &str) -> Result<u64, String> {
+    u64::from_str_radix(text, 16).map_err(|_| format!("Invalid session '{}'", text))
+}
+
+fn parse_time(text: &str) -> Result<Duration, String> {
+    text.parse()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("Invalid time '{}'", text))
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Hello { version, name } => write!(f, "hello {} {}", version, name),
+            Message::Watch { version, name } => write!(f, "watch {} {}", version, name),
+            Message::Resume {
+                version,
+                session,
+                name,
+            } => write!(f, "resume {} {:x} {}", version, session, name),
+            Message::Welcome {
+                version,
+                side,
+                session,
+                green_time,
+                red_time,
+                rules,
+                start,
+                moves,
+            } => {
+                let side = match side {
+                    Some(Side::Green) => "green",
+                    Some(Side::Red) => "red",
+                    None => "none",
+                };
+                let session = match session {
+                    Some(session) => format!("{:x}", session),
+                    None => String::from("none"),
+                };
+                write!(
+                    f,
+                    "welcome {} {} {} {} {} {} {}",
+                    version,
+                    side,
+                    session,
+                    green_time.as_millis(),
+                    red_time.as_millis(),
+                    variant_name(*rules),
+                    write_fen(start)
+                )?;
+                for mv in moves {
+                    write!(f, " {}", mv)?;
+                }
+                Ok(())
+            }
+            Message::Move(mv) => write!(f, "move {}", mv),
+            Message::Refused(reason) => write!(f, "refused {}", reason),
+            Message::Chat { name, text } => write!(f, "chat {}: {}", name, text),
+            Message::Forfeit(Side::Green) => write!(f, "forfeit green"),
+            Message::Forfeit(Side::Red) => write!(f, "forfeit red"),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Games between two players on different machines. One instance hosts and keeps the game that
+//! counts, the other joins by address, and anyone else may connect to watch. They exchange the
+//! lines of `Message` over TCP. A guest whose connection drops may come back to the same
+//! session for a while, the host keeps its seat and the game meanwhile.
+
+mod clocks;
+mod guest;
+mod host;
+mod message;
+mod peer;
+mod spectator;
+
+pub use clocks::Clocks;
+pub use guest::Guest;
+pub use host::Host;
+pub use message::{Message, VERSION};
+pub use peer::Peer;
+pub use spectator::Spectator;
+
+use crate::notation::parse_move;
+use crate::rules::{Board, Game, Move, Side};
+
+/// What happened in a network game since it was last looked at.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NetEvent {
+    /// A guest with this name joined.
+    Joined(String),
+    /// A move to play, the local player's as well as the opponent's.
+    Moved(Move),
+    /// The host would not take the local player's move.
+    Refused(String),
+    /// The connection to the opponent dropped, it may still come back.
+    Lost,
+    /// The connection is back after being lost, with every move missed already handed out.
+    Resumed,
+    /// The opponent went away for good. The host has them forfeit, a guest left by the host
+    /// has no one to settle the game with and leaves it unfinished.
+    Left,
+    /// The other end sent something that makes no sense, such as an illegal move. The
+    /// connection to it is closed and the game can not go on.
+    Failed(String),
+    /// A chat line, the local player's own lines included once the host has passed them on.
+    Chat { name: String, text: String },
+}
+
+/// One end of a network game. A move of the local player is sent with `send_move` and, like
+/// the opponent's, only played once `poll` hands it back, so that the host has checked it.
+pub trait Link {
+    /// The side played on this machine.
+    fn side(&self) -> Side;
+
+    /// Whether this end keeps the game, and so decides how it ends.
+    fn is_host(&self) -> bool;
+
+    fn is_connected(&self) -> bool;
+
+    fn send_move(&mut self, mv: &Move) -> Result<(), String>;
+
+    fn send_chat(&mut self, text: &str) -> Result<(), String>;
+
+    /// The time both sides have used, as the host keeps it.
+    fn clocks(&self) -> Clocks;
+
+    /// The next thing that happened, `None` when nothing did.
+    fn poll(&mut self) -> Result<Option<NetEvent>, String>;
+}
+
+// the game of a welcome, from the moves as they were written
+fn replay(start: Board, moves: &[String]) -> Result<Game, String> {
+    let mut game = Game::new(start);
+    for text in moves {
+        let mv = parse_move(game.board(), text)?;
+        game.play(&mv);
+    }
+    Ok(game)
+}
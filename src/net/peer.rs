@@ -0,0 +1,76 @@
+use crate::net::Message;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// One end of a network game connection.
+pub struct Peer {
+    stream: TcpStream,
+    lines: Receiver<String>,
+}
+
+impl Peer {
+    pub fn new(stream: TcpStream) -> Result<Peer, String> {
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+        let input = stream.try_clone().map_err(|e| e.to_string())?;
+
+        // lines are read on their own thread so that the game can look for them every frame
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(input).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Peer { stream, lines })
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        writeln!(self.stream, "{}", message)
+            .and_then(|_| self.stream.flush())
+            .map_err(|e| e.to_string())
+    }
+
+    /// The next message if one has arrived, an error once the other end is gone. Lines that are
+    /// not understood are skipped.
+    pub fn try_receive(&mut self) -> Result<Option<Message>, String> {
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => {
+                    if let Ok(message) = Message::parse(&line) {
+                        return Ok(Some(message));
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(String::from("The connection was closed"))
+                }
+            }
+        }
+    }
+
+    /// Hangs up, the other end sees the connection closed.
+    pub fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    /// Waits up to `timeout` for the next message.
+    pub fn receive(&mut self, timeout: Duration) -> Result<Option<Message>, String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Message::parse(&line).map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(String::from("The connection was closed")),
+        }
+    }
+}
+
+impl Drop for Peer {
+    // also ends the reading thread
+    fn drop(&mut self) {
+        self.close();
+    }
+}
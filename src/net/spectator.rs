@@ -0,0 +1,90 @@
+use crate::net::{replay, Message, NetEvent, Peer, VERSION};
+use crate::notation::parse_move;
+use crate::rules::Game;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+// how long the host may take to let us in
+const WATCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Follows a hosted game and its chat without taking part in either.
+pub struct Spectator {
+    peer: Peer,
+    game: Game,
+    is_connected: bool,
+}
+
+impl Spectator {
+    pub fn watch<A: ToSocketAddrs>(address: A, name: &str) -> Result<Spectator, String> {
+        let stream = TcpStream::connect(address).map_err(|e| e.to_string())?;
+        let mut peer = Peer::new(stream)?;
+        peer.send(&Message::Watch {
+            version: VERSION,
+            name: String::from(name),
+        })?;
+
+        match peer.receive(WATCH_TIMEOUT)? {
+            Some(Message::Welcome {
+                version,
+                side: None,
+                start,
+                moves,
+                ..
+            }) if version == VERSION => Ok(Spectator {
+                peer,
+                game: replay(start, &moves)?,
+                is_connected: true,
+            }),
+            Some(Message::Welcome { version, .. }) if version != VERSION => Err(format!(
+                "The host speaks version {} of the protocol",
+                version
+            )),
+            Some(Message::Refused(reason)) => Err(reason),
+            Some(message) => Err(format!("Unexpected answer '{}'", message)),
+            None => Err(String::from("The host did not answer")),
+        }
+    }
+
+    /// The game as it stood when joining, with every move since.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    /// The next move played or chat line said, `NetEvent::Left` once the host is gone.
+    pub fn poll(&mut self) -> Result<Option<NetEvent>, String> {
+        if !self.is_connected {
+            return Ok(None);
+        }
+
+        match self.peer.try_receive() {
+            Ok(Some(Message::Move(text))) => match parse_move(self.game.board(), &text) {
+                Ok(mv) => {
+                    self.game.play(&mv);
+                    Ok(Some(NetEvent::Moved(mv)))
+                }
+                Err(e) => {
+                    self.peer.close();
+                    self.is_connected = false;
+                    Ok(Some(NetEvent::Failed(format!(
+                        "The host played badly: {}",
+                        e
+                    ))))
+                }
+            },
+            Ok(Some(Message::Chat { name, text })) => Ok(Some(NetEvent::Chat { name, text })),
+            Ok(Some(Message::Forfeit(side))) => {
+                self.game.forfeit(side);
+                Ok(Some(NetEvent::Left))
+            }
+            Ok(_) => Ok(None),
+            Err(_) => {
+                self.is_connected = false;
+                Ok(Some(NetEvent::Left))
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::rules::{Board, Piece, Position, Side};
+
+/// Reads a draughts FEN such as `W:W21,22,K30:B1,2,K5`. Black is green and white is red.
+pub fn parse_fen(text: &str) -> Result<Board, String> {
+    let text = text.trim().trim_end_matches('.');
+    let mut fields = text.split(':');
+
+    let turn = match fields.next().map(str::trim) {
+        Some("B") => Side::Green,
+        Some("W") => Side::Red,
+        _ => return Err(format!("Invalid side to move in FEN '{}'", text)),
+    };
+    let mut board = Board::empty(turn);
+
+    for field in fields {
+        let field = field.trim();
+        let side = match field.chars().next() {
+            Some('B') => Side::Green,
+            Some('W') => Side::Red,
+            _ => return Err(format!("Invalid piece list '{}' in FEN", field)),
+        };
+
+        for item in field[1..].split(',').map(str::trim) {
+            if item.is_empty() {
+                continue;
+            }
+
+            let (piece, squares) = match item.strip_prefix('K') {
+                Some(squares) => (Piece::king(side), squares),
+                None => (Piece::man(side), item),
+            };
+
+            // ranges such as 1-12 are allowed for whole rows of pieces
+            let mut bounds = squares.splitn(2, '-').map(|square| {
+                square
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|square| Position::from_square(*square).is_some())
+                    .ok_or_else(|| format!("Invalid square '{}' in FEN", item))
+            });
+            let first = bounds.next().unwrap_or(Ok(0))?;
+            let last = bounds.next().unwrap_or(Ok(first))?;
+
+            for square in first..=last {
+                if let Some(pos) = Position::from_square(square) {
+                    board.set_piece(pos, Some(piece));
+                }
+            }
+        }
+    }
+
+    Ok(board)
+}
+
+pub fn write_fen(board: &Board) -> String {
+    let turn = match board.turn() {
+        Side::Green => "B",
+        Side::Red => "W",
+    };
+    format!(
+        "{}:W{}:B{}",
+        turn,
+        piece_list(board, Side::Red),
+        piece_list(board, Side::Green)
+    )
+}
+
+fn piece_list(board: &Board, side: Side) -> String {
+    let mut pieces: Vec<(usize, Piece)> = board
+        .pieces()
+        .filter(|(_, piece)| piece.side == side)
+        .filter_map(|(pos, piece)| pos.square().map(|square| (square, piece)))
+        .collect();
+    pieces.sort_by_key(|(square, _)| *square);
+
+    pieces
+        .iter()
+        .map(|(square, piece)| {
+            if piece.is_king() {
+                format!("K{}", square)
+            } else {
+                square.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_writes_pieces_and_kings() {
+        let fen = "W:W21,22,K30:B1,2,K5";
+        let board = parse_fen(fen).unwrap();
+        assert_eq!(board.turn(), Side::Red);
+        assert_eq!(board.count(Side::Red), 3);
+        assert_eq!(board.count(Side::Green), 3);
+        let square = |square| Position::from_square(square).unwrap();
+        assert_eq!(board.piece_at(square(30)), Some(Piece::king(Side::Red)));
+        assert_eq!(board.piece_at(square(22)), Some(Piece::man(Side::Red)));
+        assert_eq!(board.piece_at(square(5)), Some(Piece::king(Side::Green)));
+        assert_eq!(board.piece_at(square(2)), Some(Piece::man(Side::Green)));
+        assert_eq!(write_fen(&board), fen);
+    }
+
+    #[test]
+    fn reads_ranges() {
+        assert_eq!(parse_fen("B:W21-32:B1-12").unwrap(), Board::new());
+    }
+
+    #[test]
+    fn refuses_bad_input() {
+        for fen in [
+            "", "X:W21:B1", "W:W33:B1", "W:W0:B1", "W:Wa:B1", "W:W21:Q1", "W:WK:B1",
+        ] {
+            assert!(parse_fen(fen).is_err(), "{}", fen);
+        }
+    }
+}
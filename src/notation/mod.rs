@@ -0,0 +1,62 @@
+mod fen;
+mod pdn;
+
+pub use fen::{parse_fen, write_fen};
+pub use pdn::{PdnGame, PdnMove, PdnResult};
+
+use crate::rules::{Board, Move, Position};
+
+/// Writes a move in numeric notation, `11-15` or `9x18x27` listing every landing square.
+pub fn write_move(mv: &Move) -> String {
+    let separator = if mv.is_capture() { "x" } else { "-" };
+    let mut text = square_of(mv.from).to_string();
+    for pos in &mv.path {
+        text.push_str(separator);
+        text.push_str(&square_of(*pos).to_string());
+    }
+    text
+}
+
+/// Finds the legal move of `board` written in numeric notation. Captures may leave out
+/// intermediate landing squares as long as the move is not ambiguous.
+pub fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    let text = text.trim_end_matches(['!', '?']);
+    let is_capture = text.contains('x');
+    let squares = text
+        .split(['-', 'x'])
+        .map(|square| {
+            square
+                .parse::<usize>()
+                .ok()
+                .and_then(Position::from_square)
+                .ok_or_else(|| format!("Invalid square '{}' in move '{}'", square, text))
+        })
+        .collect::<Result<Vec<Position>, String>>()?;
+
+    if squares.len() < 2 {
+        return Err(format!("Invalid move '{}'", text));
+    }
+
+    let from = squares[0];
+    let to = squares[squares.len() - 1];
+    let between = &squares[1..squares.len() - 1];
+
+    let mut matching = board.legal_moves().into_iter().filter(|mv| {
+        mv.from == from && mv.to() == to && mv.is_capture() == is_capture && {
+            let mut landings = mv.path.iter();
+            between
+                .iter()
+                .all(|pos| landings.any(|landing| landing == pos))
+        }
+    });
+
+    match (matching.next(), matching.next()) {
+        (Some(mv), None) => Ok(mv),
+        (Some(_), Some(_)) => Err(format!("Ambiguous move '{}'", text)),
+        (None, _) => Err(format!("Illegal move '{}'", text)),
+    }
+}
+
+fn square_of(pos: Position) -> usize {
+    pos.square().unwrap_or(0)
+}
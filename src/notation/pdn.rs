@@ -0,0 +1,398 @@
+use crate::notation::{parse_fen, parse_move, write_fen, write_move};
+use crate::rules::{Board, DrawReason, Game, Outcome, Rules, Side};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+const LINE_WIDTH: usize = 79;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PdnResult {
+    GreenWins,
+    RedWins,
+    Draw,
+    Unknown,
+}
+
+impl PdnResult {
+    // Green moves first, so it plays the part of black in PDN
+    fn parse(token: &str) -> Option<PdnResult> {
+        match token {
+            "1-0" | "2-0" => Some(PdnResult::RedWins),
+            "0-1" | "0-2" => Some(PdnResult::GreenWins),
+            "1/2-1/2" | "1-1" => Some(PdnResult::Draw),
+            "*" => Some(PdnResult::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn from_outcome(outcome: Option<Outcome>) -> PdnResult {
+        match outcome {
+            Some(Outcome::Win(Side::Green)) => PdnResult::GreenWins,
+            Some(Outcome::Win(Side::Red)) => PdnResult::RedWins,
+            Some(Outcome::Draw(_)) => PdnResult::Draw,
+            None => PdnResult::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for PdnResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let token = match self {
+            PdnResult::GreenWins => "0-1",
+            PdnResult::RedWins => "1-0",
+            PdnResult::Draw => "1/2-1/2",
+            PdnResult::Unknown => "*",
+        };
+        write!(f, "{}", token)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PdnMove {
+    pub text: String,
+    pub comment: Option<String>,
+}
+
+/// One game of a Portable Draughts Notation file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PdnGame {
+    pub tags: Vec<(String, String)>,
+    /// Comment given before the first move.
+    pub comment: Option<String>,
+    pub moves: Vec<PdnMove>,
+    pub result: PdnResult,
+}
+
+impl Default for PdnGame {
+    fn default() -> PdnGame {
+        PdnGame {
+            tags: vec![],
+            comment: None,
+            moves: vec![],
+            result: PdnResult::Unknown,
+        }
+    }
+}
+
+impl PdnGame {
+    pub fn from_game(game: &Game) -> PdnGame {
+        let result = PdnResult::from_outcome(game.outcome());
+        let mut pdn = PdnGame {
+            moves: game
+                .moves()
+                .map(|mv| PdnMove {
+                    text: write_move(mv),
+                    comment: None,
+                })
+                .collect(),
+            result,
+            ..PdnGame::default()
+        };
+
+        pdn.set_tag("Event", "rust-checkers game");
+        pdn.set_tag("Date", "????.??.??");
+        pdn.set_tag("Black", "Green");
+        pdn.set_tag("White", "Red");
+        pdn.set_tag("Result", &result.to_string());
+        pdn.set_tag("GameType", "21");
+
+        if *game.start() != Board::new() {
+            pdn.set_tag("SetUp", "1");
+            pdn.set_tag("FEN", &write_fen(game.start()));
+        }
+
+        if let Some(Outcome::Draw(reason)) = game.outcome() {
+            let reason = match reason {
+                DrawReason::Repetition => "Drawn by repetition",
+                DrawReason::MoveLimit => "Drawn by the move limit",
+                DrawReason::Agreement => "Drawn by agreement",
+            };
+            if let Some(last) = pdn.moves.last_mut() {
+                last.comment = Some(reason.to_string());
+            }
+        }
+
+        pdn
+    }
+
+    /// Replays the moves from the opening position, or from the FEN tag when there is one.
+    pub fn to_game(&self) -> Result<Game, String> {
+        self.to_game_with_rules(Rules::default())
+    }
+
+    /// As `to_game`, with the moves checked against `rules`. A game the moves leave unfinished
+    /// ends as its result says, by agreement or by the loser giving up.
+    pub fn to_game_with_rules(&self, rules: Rules) -> Result<Game, String> {
+        let start = match self.tag("FEN") {
+            Some(fen) => parse_fen(fen)?,
+            None => Board::new(),
+        };
+        let mut game = Game::new(start.with_rules(rules));
+        for (ply, pdn_move) in self.moves.iter().enumerate() {
+            let mv = parse_move(game.board(), &pdn_move.text)
+                .map_err(|e| format!("{} at ply {}", e, ply + 1))?;
+            game.play(&mv);
+        }
+
+        let result = match self.result {
+            PdnResult::Unknown => self.tag("Result").and_then(PdnResult::parse),
+            result => Some(result),
+        };
+        match result {
+            Some(PdnResult::GreenWins) => game.forfeit(Side::Red),
+            Some(PdnResult::RedWins) => game.forfeit(Side::Green),
+            Some(PdnResult::Draw) => game.agree_draw(),
+            Some(PdnResult::Unknown) | None => {}
+        }
+        Ok(game)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(key, _)| key == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<PdnGame, String> {
+        PdnGame::parse_all(text)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| String::from("No game found"))
+    }
+
+    /// Reads every game of a PDN collection.
+    pub fn parse_all(text: &str) -> Result<Vec<PdnGame>, String> {
+        let mut games = vec![];
+        let mut game = PdnGame::default();
+        let mut has_content = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '[' => {
+                    // tags after moves start the next game
+                    if !game.moves.is_empty() {
+                        games.push(std::mem::take(&mut game));
+                    }
+                    let (name, value) = parse_tag(&read_until(&mut chars, ']')?)?;
+                    game.tags.push((name, value));
+                    has_content = true;
+                }
+                '{' => {
+                    let comment = read_until(&mut chars, '}')?.trim().to_string();
+                    match game.moves.last_mut() {
+                        Some(last) => last.comment = Some(comment),
+                        None => game.comment = Some(comment),
+                    }
+                }
+                '(' => skip_variation(&mut chars)?,
+                c if c.is_whitespace() => {}
+                c => {
+                    let mut token = c.to_string();
+                    while let Some(next) = chars.peek() {
+                        if next.is_whitespace() || "[{(".contains(*next) {
+                            break;
+                        }
+                        token.push(*next);
+                        chars.next();
+                    }
+
+                    if let Some(result) = PdnResult::parse(&token) {
+                        game.result = result;
+                        games.push(std::mem::take(&mut game));
+                        has_content = false;
+                    } else if let Some(text) = move_text(&token) {
+                        game.moves.push(PdnMove {
+                            text: text.to_string(),
+                            comment: None,
+                        });
+                        has_content = true;
+                    }
+                }
+            }
+        }
+
+        if has_content {
+            games.push(game);
+        }
+        Ok(games)
+    }
+}
+
+impl fmt::Display for PdnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('"', "'"))?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = vec![];
+        if let Some(comment) = &self.comment {
+            tokens.push(format!("{{{}}}", comment));
+        }
+        for (ply, mv) in self.moves.iter().enumerate() {
+            if ply % 2 == 0 {
+                tokens.push(format!("{}.", ply / 2 + 1));
+            }
+            tokens.push(mv.text.clone());
+            if let Some(comment) = &mv.comment {
+                tokens.push(format!("{{{}}}", comment));
+            }
+        }
+        tokens.push(self.result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+fn read_until(chars: &mut Peekable<Chars>, end: char) -> Result<String, String> {
+    let mut text = String::new();
+    for c in chars {
+        if c == end {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+    Err(format!("Missing '{}' in PDN", end))
+}
+
+fn skip_variation(chars: &mut Peekable<Chars>) -> Result<(), String> {
+    let mut depth = 1;
+    while depth > 0 {
+        match chars.next() {
+            Some('(') => depth += 1,
+            Some(')') => depth -= 1,
+            Some('{') => {
+                read_until(chars, '}')?;
+            }
+            Some(_) => {}
+            None => return Err(String::from("Unterminated variation in PDN")),
+        }
+    }
+    Ok(())
+}
+
+fn parse_tag(text: &str) -> Result<(String, String), String> {
+    let text = text.trim();
+    let split = text
+        .find(char::is_whitespace)
+        .ok_or_else(|| format!("Invalid tag [{}]", text))?;
+    let (name, value) = text.split_at(split);
+    let value = value.trim().trim_matches('"');
+    Ok((name.to_string(), value.to_string()))
+}
+
+// strips a leading move number such as "12." or "12..." and ignores annotation glyphs
+fn move_text(token: &str) -> Option<&str> {
+    let token = match token.rfind('.') {
+        Some(dot) => &token[dot + 1..],
+        None => token,
+    };
+    let is_move = !token.is_empty()
+        && token.starts_with(|c: char| c.is_ascii_digit())
+        && (token.contains('-') || token.contains('x'));
+    if is_move {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENING: &str = r#"[Event "Club night"]
+[Black "Green"]
+[White "Red"]
+[Result "*"]
+
+{A quiet start} 1. 11-15 24-19 {offering a man} 2. 15x24 28x19 *
+"#;
+
+    #[test]
+    fn reads_tags_comments_and_captures() {
+        let pdn = PdnGame::parse(OPENING).unwrap();
+        assert_eq!(pdn.tag("Event"), Some("Club night"));
+        assert_eq!(pdn.tag("White"), Some("Red"));
+        assert_eq!(pdn.comment.as_deref(), Some("A quiet start"));
+        let moves: Vec<&str> = pdn.moves.iter().map(|mv| mv.text.as_str()).collect();
+        assert_eq!(moves, ["11-15", "24-19", "15x24", "28x19"]);
+        assert_eq!(pdn.moves[1].comment.as_deref(), Some("offering a man"));
+        assert_eq!(pdn.result, PdnResult::Unknown);
+
+        assert_eq!(PdnGame::parse(&pdn.to_string()).unwrap(), pdn);
+        assert_eq!(pdn.to_game().unwrap().ply(), 4);
+    }
+
+    #[test]
+    fn writes_and_reads_back_a_finished_game() {
+        let mut game = Game::new(parse_fen("B:W14,23:B9").unwrap());
+        let mv = parse_move(game.board(), "9x18x27").unwrap();
+        game.play(&mv);
+        assert_eq!(game.outcome(), Some(Outcome::Win(Side::Green)));
+
+        let pdn = PdnGame::from_game(&game);
+        let text = pdn.to_string();
+        assert!(text.contains("1. 9x18x27 0-1"));
+
+        let read = PdnGame::parse(&text).unwrap();
+        assert_eq!(read, pdn);
+        assert_eq!(read.result, PdnResult::GreenWins);
+        let replayed = read.to_game().unwrap();
+        assert_eq!(replayed.board(), game.board());
+        assert_eq!(replayed.outcome(), game.outcome());
+    }
+
+    #[test]
+    fn ends_the_game_as_its_result_says() {
+        let pdn = PdnGame::parse("1. 11-15 24-19 1/2-1/2").unwrap();
+        let game = pdn.to_game().unwrap();
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.outcome(), Some(Outcome::Draw(DrawReason::Agreement)));
+
+        let pdn = PdnGame::parse("[Result \"1-0\"]\n1. 11-15 *").unwrap();
+        assert_eq!(
+            pdn.to_game().unwrap().outcome(),
+            Some(Outcome::Win(Side::Red))
+        );
+
+        let pdn = PdnGame::parse("1. 11-15 24-19 0-1").unwrap();
+        assert_eq!(
+            pdn.to_game().unwrap().outcome(),
+            Some(Outcome::Win(Side::Green))
+        );
+    }
+
+    #[test]
+    fn refuses_malformed_movetext() {
+        assert!(PdnGame::parse("1. 11-15 {never closed").is_err());
+        assert!(PdnGame::parse("[Event \"Club night\"").is_err());
+        assert!(PdnGame::parse("1. 11-15 (24-20 *").is_err());
+
+        for text in ["1. 11-17 *", "1. 11-15 24-1x9 *", "1. 11x15 *"] {
+            let pdn = PdnGame::parse(text).unwrap();
+            assert!(pdn.to_game().is_err(), "{}", text);
+        }
+    }
+}
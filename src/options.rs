@@ -0,0 +1,224 @@
+use checkers::rules::{variant_rules, Rules, Side};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: checkers-game [--load <game.pdn> | --fen <position>]
+                     [--variant <normal|majority>] [--computer <green|red>]... [--engine <alphabeta|mcts|hub>]
+                     [--hub <engine program>] [--hub-mode]
+                     [--dxp-connect <host:port> | --dxp-listen <port>]
+                     [--host <port> [--side <green|red>] | --join <host:port> [--session <id>]]
+                     [--watch <host:port>]
+                     [--name <player name>]
+                     [--depth <plies>] [--iterations <playouts>] [--seed <number>]
+                     [--move-time <ms>] [--tablebase <file>] [--book <file>]";
+
+const DEFAULT_DEPTH: u32 = 20;
+const DEFAULT_ITERATIONS: u32 = 200_000;
+const DEFAULT_MOVE_TIME: Duration = Duration::from_millis(3000);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EngineStyle {
+    AlphaBeta,
+    Mcts,
+    /// An external program speaking the Hub protocol.
+    Hub,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub load: Option<PathBuf>,
+    pub fen: Option<String>,
+    /// The rules games are started with, a joined game keeps the host's.
+    pub rules: Rules,
+    pub computer: Vec<Side>,
+    pub engine: EngineStyle,
+    pub depth: u32,
+    pub iterations: u32,
+    pub seed: Option<u64>,
+    pub move_time: Duration,
+    pub tablebase: Option<PathBuf>,
+    pub book: Option<PathBuf>,
+    pub hub: Option<PathBuf>,
+    /// Runs headless as an engine speaking the Hub protocol on standard input and output.
+    pub hub_mode: bool,
+    /// Plays a DXP peer headless, asking it for a game.
+    pub dxp_connect: Option<String>,
+    /// Plays a DXP peer headless, waiting for it to ask for a game.
+    pub dxp_listen: Option<u16>,
+    /// Hosts a network game on this port for someone to join.
+    pub host: Option<u16>,
+    /// The side played by the host.
+    pub side: Side,
+    /// Joins the network game hosted at this address.
+    pub join: Option<String>,
+    /// Resumes the joined game of this session instead of taking a free seat.
+    pub session: Option<u64>,
+    /// Watches the network game hosted at this address.
+    pub watch: Option<String>,
+    pub name: String,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            load: None,
+            fen: None,
+            rules: Rules::default(),
+            computer: vec![],
+            engine: EngineStyle::AlphaBeta,
+            depth: DEFAULT_DEPTH,
+            iterations: DEFAULT_ITERATIONS,
+            seed: None,
+            move_time: DEFAULT_MOVE_TIME,
+            tablebase: None,
+            book: None,
+            hub: None,
+            hub_mode: false,
+            dxp_connect: None,
+            dxp_listen: None,
+            host: None,
+            side: Side::Green,
+            join: None,
+            session: None,
+            watch: None,
+            name: String::from("Player"),
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                "--load" => options.load = Some(PathBuf::from(value_of(&arg, args.next())?)),
+                "--fen" => options.fen = Some(value_of(&arg, args.next())?),
+                "--variant" => {
+                    let variant = value_of(&arg, args.next())?;
+                    options.rules = variant_rules(&variant)
+                        .ok_or_else(|| format!("Unknown variant '{}'\n{}", variant, USAGE))?
+                }
+                "--computer" => options
+                    .computer
+                    .push(side_of(&value_of(&arg, args.next())?)?),
+                "--engine" => options.engine = engine_of(&value_of(&arg, args.next())?)?,
+                "--depth" => {
+                    options.depth = value_of(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| format!("Invalid depth\n{}", USAGE))?
+                }
+                "--iterations" => {
+                    options.iterations = value_of(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| format!("Invalid number of iterations\n{}", USAGE))?
+                }
+                "--seed" => {
+                    let seed = value_of(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| format!("Invalid seed\n{}", USAGE))?;
+                    options.seed = Some(seed)
+                }
+                "--move-time" => {
+                    let millis = value_of(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| format!("Invalid move time\n{}", USAGE))?;
+                    options.move_time = Duration::from_millis(millis)
+                }
+                "--tablebase" => {
+                    options.tablebase = Some(PathBuf::from(value_of(&arg, args.next())?))
+                }
+                "--book" => options.book = Some(PathBuf::from(value_of(&arg, args.next())?)),
+                "--hub-mode" => options.hub_mode = true,
+                "--hub" => {
+                    options.hub = Some(PathBuf::from(value_of(&arg, args.next())?));
+                    options.engine = EngineStyle::Hub;
+                }
+                "--dxp-connect" => options.dxp_connect = Some(value_of(&arg, args.next())?),
+                "--dxp-listen" => {
+                    let port = value_of(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| format!("Invalid port\n{}", USAGE))?;
+                    options.dxp_listen = Some(port)
+                }
+                "--host" => {
+                    let port = value_of(&arg, args.next())?
+                        .parse()
+                        .map_err(|_| format!("Invalid port\n{}", USAGE))?;
+                    options.host = Some(port)
+                }
+                "--side" => options.side = side_of(&value_of(&arg, args.next())?)?,
+                "--join" => options.join = Some(value_of(&arg, args.next())?),
+                "--session" => {
+                    let session = value_of(&arg, args.next())?;
+                    options.session = Some(
+                        u64::from_str_radix(&session, 16)
+                            .map_err(|_| format!("Invalid session\n{}", USAGE))?,
+                    )
+                }
+                "--watch" => options.watch = Some(value_of(&arg, args.next())?),
+                "--name" => options.name = value_of(&arg, args.next())?,
+                _ => return Err(format!("Unknown argument '{}'\n{}", arg, USAGE)),
+            }
+        }
+
+        if options.load.is_some() && options.fen.is_some() {
+            return Err(format!("Use either --load or --fen\n{}", USAGE));
+        }
+        if options.dxp_connect.is_some() && options.dxp_listen.is_some() {
+            return Err(format!(
+                "Use either --dxp-connect or --dxp-listen\n{}",
+                USAGE
+            ));
+        }
+        if options.session.is_some() && options.join.is_none() {
+            return Err(format!("--session goes with --join\n{}", USAGE));
+        }
+        let networks = [
+            options.host.is_some(),
+            options.join.is_some(),
+            options.watch.is_some(),
+        ];
+        if networks.iter().filter(|network| **network).count() > 1 {
+            return Err(format!(
+                "Use only one of --host, --join and --watch\n{}",
+                USAGE
+            ));
+        }
+        if networks.contains(&true) && !options.computer.is_empty() {
+            return Err(format!(
+                "Network games are played by hand, leave out --computer\n{}",
+                USAGE
+            ));
+        }
+        if options.engine == EngineStyle::Hub && options.hub.is_none() {
+            return Err(format!(
+                "The hub engine needs --hub <engine program>\n{}",
+                USAGE
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
+fn value_of(arg: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("Missing value for '{}'\n{}", arg, USAGE))
+}
+
+fn side_of(value: &str) -> Result<Side, String> {
+    match value {
+        "green" => Ok(Side::Green),
+        "red" => Ok(Side::Red),
+        _ => Err(format!("Unknown side '{}'\n{}", value, USAGE)),
+    }
+}
+
+fn engine_of(value: &str) -> Result<EngineStyle, String> {
+    match value {
+        "alphabeta" => Ok(EngineStyle::AlphaBeta),
+        "mcts" => Ok(EngineStyle::Mcts),
+        "hub" => Ok(EngineStyle::Hub),
+        _ => Err(format!("Unknown engine '{}'\n{}", value, USAGE)),
+    }
+}
@@ -0,0 +1,408 @@
+use crate::rules::zobrist::{piece_key, TURN_KEY};
+use crate::rules::{
+    CaptureRule, Move, MoveError, Piece, Position, Rules, Selection, Side, BOARD_LENGTH, BOARD_SIZE,
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Board {
+    cells: [Option<Piece>; BOARD_SIZE],
+    turn: Side,
+    rules: Rules,
+    key: u64,
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
+    }
+}
+
+impl Board {
+    /// The opening layout, green to move.
+    pub fn new() -> Board {
+        let mut board = Board::empty(Side::Green);
+
+        for index in 0..BOARD_SIZE {
+            let pos = Position::from_index(index).unwrap();
+            if !pos.is_playable() {
+                continue;
+            }
+
+            if pos.row() < (BOARD_LENGTH / 2 - 1) {
+                board.set_piece(pos, Some(Piece::man(Side::Green)));
+            } else if pos.row() > (BOARD_LENGTH / 2) {
+                board.set_piece(pos, Some(Piece::man(Side::Red)));
+            }
+        }
+
+        board
+    }
+
+    pub fn empty(turn: Side) -> Board {
+        Board {
+            cells: [None; BOARD_SIZE],
+            turn,
+            rules: Rules::default(),
+            key: if turn.is_red() { TURN_KEY } else { 0 },
+        }
+    }
+
+    pub fn with_rules(mut self, rules: Rules) -> Board {
+        self.rules = rules;
+        self
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn turn(&self) -> Side {
+        self.turn
+    }
+
+    pub fn set_turn(&mut self, turn: Side) {
+        if turn != self.turn {
+            self.switch_turn();
+        }
+    }
+
+    fn switch_turn(&mut self) {
+        self.turn = self.turn.opponent();
+        self.key ^= TURN_KEY;
+    }
+
+    /// Zobrist key of the pieces and the side to move, kept up to date as the board changes.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    pub fn piece_at(&self, pos: Position) -> Option<Piece> {
+        self.cells[pos.index()]
+    }
+
+    pub fn set_piece(&mut self, pos: Position, piece: Option<Piece>) {
+        let cell = &mut self.cells[pos.index()];
+        if let Some(old) = *cell {
+            self.key ^= piece_key(pos, old);
+        }
+        if let Some(new) = piece {
+            self.key ^= piece_key(pos, new);
+        }
+        *cell = piece;
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        self.cells.iter().enumerate().filter_map(|(index, cell)| {
+            cell.map(|piece| (Position::from_index(index).unwrap(), piece))
+        })
+    }
+
+    pub fn count(&self, side: Side) -> usize {
+        self.pieces()
+            .filter(|(_, piece)| piece.side == side)
+            .count()
+    }
+
+    /// Every legal move for the side to move, with the capture obligation applied.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for (pos, piece) in self.pieces() {
+            if piece.side == self.turn {
+                self.piece_moves(pos, piece, &mut moves);
+            }
+        }
+
+        let most = moves.iter().map(Move::capture_count).max().unwrap_or(0);
+        match self.rules.capture {
+            CaptureRule::Mandatory if most > 0 => moves.retain(Move::is_capture),
+            CaptureRule::Majority => moves.retain(|mv| mv.capture_count() == most),
+            _ => {}
+        }
+        moves
+    }
+
+    /// The legal moves of the piece on `from`.
+    pub fn moves_from(&self, from: Position) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.retain(|mv| mv.from == from);
+        moves
+    }
+
+    fn piece_moves(&self, from: Position, piece: Piece, moves: &mut Vec<Move>) {
+        let mut path = Vec::new();
+        let mut captured = Vec::new();
+        self.capture_chains(from, piece, from, &mut path, &mut captured, moves);
+
+        for direction in piece.directions() {
+            if let Some(next) = from.step(*direction, 1) {
+                if self.piece_at(next).is_none() {
+                    moves.push(Move::new(from, vec![next], vec![], piece));
+                }
+            }
+        }
+    }
+
+    // the jumping piece keeps going until it runs out of captures. Captured pieces stay on the
+    // board until the move is over, so they block landing but can not be jumped twice
+    fn capture_chains(
+        &self,
+        from: Position,
+        piece: Piece,
+        at: Position,
+        path: &mut Vec<Position>,
+        captured: &mut Vec<(Position, Piece)>,
+        moves: &mut Vec<Move>,
+    ) {
+        // a man reaching the far row is crowned and that ends its move
+        let crowned = !piece.is_king() && at.row() == piece.side.promotion_row();
+        let mut extended = false;
+
+        if !crowned {
+            for direction in piece.directions() {
+                let (next, landing) = match (at.step(*direction, 1), at.step(*direction, 2)) {
+                    (Some(next), Some(landing)) => (next, landing),
+                    _ => continue,
+                };
+
+                let victim = match self.piece_at(next) {
+                    Some(victim) if victim.side != piece.side => victim,
+                    _ => continue,
+                };
+                let is_taken = captured.iter().any(|(pos, _)| *pos == next);
+                let is_free = landing == from || self.piece_at(landing).is_none();
+
+                if !is_taken && is_free {
+                    path.push(landing);
+                    captured.push((next, victim));
+                    self.capture_chains(from, piece, landing, path, captured, moves);
+                    path.pop();
+                    captured.pop();
+                    extended = true;
+                }
+            }
+        }
+
+        if !extended && !path.is_empty() {
+            moves.push(Move::new(from, path.clone(), captured.clone(), piece));
+        }
+    }
+
+    /// Matches the landing squares clicked so far against the legal moves of the piece on
+    /// `from`. A capture chain is only complete once every landing square has been given.
+    pub fn find_move(&self, from: Position, path: &[Position]) -> Result<Selection, MoveError> {
+        let piece = self.piece_at(from).ok_or(MoveError::EmptySource)?;
+        if piece.side != self.turn {
+            return Err(MoveError::NotYourTurn);
+        }
+        if path.is_empty() {
+            return Ok(Selection::Partial);
+        }
+
+        let candidates: Vec<Move> = self
+            .moves_from(from)
+            .into_iter()
+            .filter(|mv| mv.path.starts_with(path))
+            .collect();
+
+        if let Some(mv) = candidates.iter().find(|mv| mv.path.len() == path.len()) {
+            return Ok(Selection::Complete(mv.clone()));
+        }
+        if !candidates.is_empty() {
+            return Ok(Selection::Partial);
+        }
+
+        let to = path[0];
+        if self.moves_from(from).iter().any(|mv| mv.path[0] == to) {
+            return Err(MoveError::BrokenChain);
+        }
+
+        // the move may exist for the piece but be ruled out by the capture obligation
+        let mut unrestricted = Vec::new();
+        self.piece_moves(from, piece, &mut unrestricted);
+        match unrestricted.into_iter().find(|mv| mv.path[0] == to) {
+            Some(mv) if mv.is_capture() => Err(MoveError::MajorityCaptureRequired),
+            Some(_) => Err(MoveError::CaptureRequired),
+            None => Err(self.explain_illegal(from, to, piece)),
+        }
+    }
+
+    fn explain_illegal(&self, from: Position, to: Position, piece: Piece) -> MoveError {
+        if self.piece_at(to).is_some() {
+            return MoveError::Occupied;
+        }
+
+        let rows = to.row() as i32 - from.row() as i32;
+        let columns = to.column() as i32 - from.column() as i32;
+        if rows == 0 || rows.abs() != columns.abs() {
+            MoveError::NotDiagonal
+        } else if !piece.is_king() && rows.signum() != piece.side.forward() {
+            MoveError::Backwards
+        } else if rows.abs() == 2 {
+            MoveError::NothingToCapture
+        } else {
+            MoveError::TooFar
+        }
+    }
+
+    /// Plays a move previously produced for this board and hands over the turn.
+    pub fn apply(&mut self, mv: &Move) {
+        if let Some(piece) = self.piece_at(mv.from) {
+            let piece = if mv.promotes { piece.crowned() } else { piece };
+            self.set_piece(mv.from, None);
+            self.set_piece(mv.to(), Some(piece));
+        }
+
+        for (victim, _) in &mv.captured {
+            self.set_piece(*victim, None);
+        }
+
+        self.switch_turn();
+    }
+
+    /// Takes back a move that was the last one applied to this board.
+    pub fn unapply(&mut self, mv: &Move) {
+        if let Some(piece) = self.piece_at(mv.to()) {
+            let piece = if mv.promotes {
+                Piece::man(piece.side)
+            } else {
+                piece
+            };
+            self.set_piece(mv.to(), None);
+            self.set_piece(mv.from, Some(piece));
+        }
+
+        for (victim, piece) in &mv.captured {
+            self.set_piece(*victim, Some(*piece));
+        }
+
+        self.switch_turn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{parse_fen, write_move};
+
+    #[test]
+    fn green_has_seven_moves_from_the_start() {
+        let board = Board::new();
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 7);
+        for mv in &moves {
+            assert_eq!(board.piece_at(mv.from), Some(Piece::man(Side::Green)));
+            assert!(!mv.is_capture());
+            assert_eq!(mv.to().row(), mv.from.row() + 1);
+        }
+        assert_eq!(board.count(Side::Green), 12);
+        assert_eq!(board.count(Side::Red), 12);
+    }
+
+    #[test]
+    fn unapply_takes_back_apply() {
+        // down a fixed line of play, every move is tried and taken back on the way
+        let mut board = Board::new();
+        for ply in 0..80 {
+            let moves = board.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            for mv in &moves {
+                let before = board;
+                board.apply(mv);
+                assert_ne!(board, before);
+                board.unapply(mv);
+                assert_eq!(board, before);
+            }
+            board.apply(&moves[ply % moves.len()]);
+        }
+    }
+
+    fn square(square: usize) -> Position {
+        Position::from_square(square).unwrap()
+    }
+
+    #[test]
+    fn a_quiet_move_is_refused_while_a_jump_is_there() {
+        let board = parse_fen("B:W14:B1,9").unwrap();
+        assert!(board.legal_moves().iter().all(Move::is_capture));
+
+        for (from, to) in [(1, 5), (9, 13)] {
+            let refusal = board.find_move(square(from), &[square(to)]);
+            assert_eq!(refusal, Err(MoveError::CaptureRequired));
+        }
+        assert_eq!(
+            MoveError::CaptureRequired.to_string(),
+            "A capture is available and must be played"
+        );
+        assert!(matches!(
+            board.find_move(square(9), &[square(18)]),
+            Ok(Selection::Complete(_))
+        ));
+    }
+
+    #[test]
+    fn the_majority_rule_refuses_the_shorter_chain() {
+        // 9x18x27 takes two pieces, 10x17 only one
+        let fen = "B:W14,23:B9,10";
+        let board = parse_fen(fen).unwrap();
+        assert_eq!(board.legal_moves().len(), 2);
+        assert!(matches!(
+            board.find_move(square(10), &[square(17)]),
+            Ok(Selection::Complete(_))
+        ));
+
+        let board = parse_fen(fen).unwrap().with_rules(Rules {
+            capture: CaptureRule::Majority,
+        });
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].capture_count(), 2);
+        assert_eq!(
+            board.find_move(square(10), &[square(17)]),
+            Err(MoveError::MajorityCaptureRequired)
+        );
+    }
+
+    fn written(board: &Board) -> Vec<String> {
+        let mut moves: Vec<String> = board.legal_moves().iter().map(write_move).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn jumps_go_on_while_there_is_something_to_take() {
+        assert_eq!(written(&parse_fen("B:W14,23:B9").unwrap()), ["9x18x27"]);
+        assert_eq!(
+            written(&parse_fen("B:W6,15,23:B1").unwrap()),
+            ["1x10x19x26"]
+        );
+    }
+
+    #[test]
+    fn crowning_ends_the_chain() {
+        // as a king the piece could go on over 27, as a man it stops on the far row
+        let board = parse_fen("B:W26,27:B22").unwrap();
+        let moves = board.legal_moves();
+        assert_eq!(written(&board), ["22x31"]);
+        assert!(moves[0].promotes);
+    }
+
+    #[test]
+    fn captured_pieces_stay_until_the_move_is_over() {
+        // the king goes round and lands where it started, next to 15 which it already took
+        let mut board = parse_fen("B:W15,16,23,24:BK11").unwrap();
+        assert_eq!(written(&board), ["11x18x27x20x11", "11x20x27x18x11"]);
+
+        let before = board;
+        for mv in board.legal_moves() {
+            assert_eq!(mv.capture_count(), 4);
+            assert_eq!(mv.to(), mv.from);
+            board.apply(&mv);
+            assert_eq!(board.count(Side::Red), 0);
+            assert_eq!(board.piece_at(square(11)), Some(Piece::king(Side::Green)));
+            board.unapply(&mv);
+            assert_eq!(board, before);
+        }
+    }
+}
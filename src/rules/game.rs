@@ -0,0 +1,397 @@
+use crate::rules::{Board, Move, Side};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DrawReason {
+    Repetition,
+    MoveLimit,
+    Agreement,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Outcome {
+    /// The other side was left without a legal move, or gave up.
+    Win(Side),
+    Draw(DrawReason),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DrawRules {
+    /// Times the same position must occur for a draw, `None` to never draw by repetition.
+    pub repetitions: Option<usize>,
+    /// Moves per side without a capture or a man moving before the game is drawn.
+    pub move_limit: Option<usize>,
+}
+
+impl Default for DrawRules {
+    fn default() -> DrawRules {
+        DrawRules {
+            repetitions: Some(3),
+            move_limit: Some(40),
+        }
+    }
+}
+
+// what is needed to take a move back
+#[derive(Debug, Clone)]
+struct Record {
+    mv: Move,
+    quiet_plies: usize,
+    draw_offer: Option<Side>,
+}
+
+/// A board together with its move history and what is needed to decide when the game is over.
+#[derive(Debug, Clone)]
+pub struct Game {
+    board: Board,
+    start: Board,
+    draw_rules: DrawRules,
+    // position keys after every ply, for spotting repetitions
+    keys: Vec<u64>,
+    history: Vec<Record>,
+    undone: Vec<Move>,
+    quiet_plies: usize,
+    draw_offer: Option<Side>,
+    outcome: Option<Outcome>,
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new(Board::new())
+    }
+}
+
+impl Game {
+    pub fn new(board: Board) -> Game {
+        let mut game = Game {
+            board,
+            start: board,
+            draw_rules: DrawRules::default(),
+            keys: vec![board.key()],
+            history: vec![],
+            undone: vec![],
+            quiet_plies: 0,
+            draw_offer: None,
+            outcome: None,
+        };
+        game.update_outcome();
+        game
+    }
+
+    pub fn with_draw_rules(mut self, draw_rules: DrawRules) -> Game {
+        self.draw_rules = draw_rules;
+        self.update_outcome();
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// The position the game started from.
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    /// The moves played so far, oldest first.
+    pub fn moves(&self) -> impl Iterator<Item = &Move> + '_ {
+        self.history.iter().map(|record| &record.mv)
+    }
+
+    pub fn ply(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Plays a legal move of the side to move. Moves that were undone can no longer be redone.
+    pub fn play(&mut self, mv: &Move) {
+        if self.is_over() {
+            return;
+        }
+
+        self.undone.clear();
+        self.push_move(mv.clone());
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Takes back the last move, restoring captured pieces, demoting a fresh king and
+    /// handing the turn back.
+    pub fn undo(&mut self) -> bool {
+        let record = match self.history.pop() {
+            Some(record) => record,
+            None => return false,
+        };
+
+        self.board.unapply(&record.mv);
+        self.keys.pop();
+        self.quiet_plies = record.quiet_plies;
+        self.draw_offer = record.draw_offer;
+        self.undone.push(record.mv);
+        self.update_outcome();
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(mv) => {
+                self.push_move(mv);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn push_move(&mut self, mv: Move) {
+        let mover = self.board.turn();
+        let is_man = match self.board.piece_at(mv.from) {
+            Some(piece) => !piece.is_king(),
+            None => false,
+        };
+        let is_irreversible = mv.is_capture() || is_man;
+
+        self.board.apply(&mv);
+        self.keys.push(self.board.key());
+        self.history.push(Record {
+            mv,
+            quiet_plies: self.quiet_plies,
+            draw_offer: self.draw_offer,
+        });
+
+        if is_irreversible {
+            self.quiet_plies = 0;
+        } else {
+            self.quiet_plies += 1;
+        }
+
+        // moving instead of answering declines the opponent's offer
+        if self.draw_offer == Some(mover.opponent()) {
+            self.draw_offer = None;
+        }
+
+        self.update_outcome();
+    }
+
+    pub fn draw_offer(&self) -> Option<Side> {
+        self.draw_offer
+    }
+
+    /// The side to move offers a draw, or accepts the one its opponent has offered.
+    pub fn offer_draw(&mut self) {
+        if self.is_over() {
+            return;
+        }
+
+        let side = self.board.turn();
+        if self.draw_offer == Some(side.opponent()) {
+            self.outcome = Some(Outcome::Draw(DrawReason::Agreement));
+        } else {
+            self.draw_offer = Some(side);
+        }
+    }
+
+    /// Both sides agree to a draw at once, as when a recorded game says it was drawn.
+    pub fn agree_draw(&mut self) {
+        if !self.is_over() {
+            self.outcome = Some(Outcome::Draw(DrawReason::Agreement));
+        }
+    }
+
+    /// `side` gives the game up, whoever is to move.
+    pub fn forfeit(&mut self, side: Side) {
+        if !self.is_over() {
+            self.outcome = Some(Outcome::Win(side.opponent()));
+        }
+    }
+
+    /// How often the current position has occurred since the last irreversible move.
+    pub fn repetitions(&self) -> usize {
+        let since = self.keys.len() - 1 - self.quiet_plies;
+        let key = self.board.key();
+        self.keys[since..].iter().filter(|k| **k == key).count()
+    }
+
+    fn update_outcome(&mut self) {
+        self.outcome = if self.board.legal_moves().is_empty() {
+            Some(Outcome::Win(self.board.turn().opponent()))
+        } else if self
+            .draw_rules
+            .repetitions
+            .is_some_and(|limit| self.repetitions() >= limit)
+        {
+            Some(Outcome::Draw(DrawReason::Repetition))
+        } else if self
+            .draw_rules
+            .move_limit
+            .is_some_and(|limit| self.quiet_plies >= limit * 2)
+        {
+            Some(Outcome::Draw(DrawReason::MoveLimit))
+        } else {
+            None
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{parse_fen, parse_move};
+    use crate::rules::{Piece, Position};
+
+    fn play(game: &mut Game, text: &str) {
+        let mv = parse_move(game.board(), text).unwrap();
+        game.play(&mv);
+    }
+
+    #[test]
+    fn a_side_without_moves_loses() {
+        let game = Game::new(parse_fen("B:W8,11:B4").unwrap());
+        assert_eq!(game.outcome(), Some(Outcome::Win(Side::Red)));
+
+        let game = Game::new(parse_fen("B:W18:B").unwrap());
+        assert_eq!(game.outcome(), Some(Outcome::Win(Side::Red)));
+        assert!(Game::new(parse_fen("B:W18:B1").unwrap())
+            .outcome()
+            .is_none());
+    }
+
+    #[test]
+    fn taking_the_last_piece_wins() {
+        let mut game = Game::new(parse_fen("B:W14:B9").unwrap());
+        play(&mut game, "9x18");
+        assert_eq!(game.outcome(), Some(Outcome::Win(Side::Green)));
+    }
+
+    #[test]
+    fn the_third_repetition_is_a_draw() {
+        let mut game = Game::new(parse_fen("B:WK32:BK1").unwrap());
+        for text in ["1-6", "32-28", "6-1", "28-32", "1-6", "32-28", "6-1"] {
+            play(&mut game, text);
+        }
+        assert_eq!(game.repetitions(), 2);
+        assert!(game.outcome().is_none());
+
+        play(&mut game, "28-32");
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.outcome(), Some(Outcome::Draw(DrawReason::Repetition)));
+    }
+
+    #[test]
+    fn forty_moves_without_a_capture_or_a_man_moving_are_a_draw() {
+        let rules = DrawRules {
+            repetitions: None,
+            ..DrawRules::default()
+        };
+        let mut game = Game::new(parse_fen("B:WK32:BK1").unwrap()).with_draw_rules(rules);
+        let shuffle = ["1-6", "32-28", "6-1", "28-32"];
+        for text in shuffle.iter().cycle().take(79) {
+            play(&mut game, text);
+        }
+        assert!(game.outcome().is_none());
+
+        play(&mut game, "28-32");
+        assert_eq!(game.outcome(), Some(Outcome::Draw(DrawReason::MoveLimit)));
+    }
+
+    #[test]
+    fn a_man_moving_starts_the_count_again() {
+        let rules = DrawRules {
+            repetitions: None,
+            move_limit: Some(2),
+        };
+        let mut game = Game::new(parse_fen("B:WK32,21:BK1").unwrap()).with_draw_rules(rules);
+        for text in ["1-6", "32-28", "6-1"] {
+            play(&mut game, text);
+        }
+        play(&mut game, "21-17");
+        for text in ["1-6", "28-32", "6-1"] {
+            play(&mut game, text);
+        }
+        assert!(game.outcome().is_none());
+
+        play(&mut game, "32-28");
+        assert_eq!(game.outcome(), Some(Outcome::Draw(DrawReason::MoveLimit)));
+    }
+
+    #[test]
+    fn a_draw_is_agreed_when_the_offer_is_answered() {
+        let mut game = Game::default();
+        game.offer_draw();
+        assert_eq!(game.draw_offer(), Some(Side::Green));
+        play(&mut game, "9-13");
+        assert!(game.outcome().is_none());
+
+        game.offer_draw();
+        assert_eq!(game.outcome(), Some(Outcome::Draw(DrawReason::Agreement)));
+    }
+
+    #[test]
+    fn moving_on_declines_the_offer() {
+        let mut game = Game::default();
+        game.offer_draw();
+        play(&mut game, "9-13");
+        play(&mut game, "22-18");
+        assert_eq!(game.draw_offer(), None);
+        assert!(game.outcome().is_none());
+    }
+
+    #[test]
+    fn undo_puts_captured_pieces_back() {
+        let start = parse_fen("B:W14,32:B9").unwrap();
+        let mut game = Game::new(start);
+        play(&mut game, "9x18");
+        assert_eq!(game.board().count(Side::Red), 1);
+
+        assert!(game.undo());
+        assert_eq!(*game.board(), start);
+        assert_eq!(game.board().turn(), Side::Green);
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn undo_takes_the_crown_back() {
+        let start = parse_fen("B:W32:B26").unwrap();
+        let mut game = Game::new(start);
+        play(&mut game, "26-31");
+        let crowned = Position::from_square(31).unwrap();
+        assert_eq!(
+            game.board().piece_at(crowned),
+            Some(Piece::king(Side::Green))
+        );
+
+        assert!(game.undo());
+        assert_eq!(*game.board(), start);
+        assert!(game.redo());
+        assert_eq!(
+            game.board().piece_at(crowned),
+            Some(Piece::king(Side::Green))
+        );
+        assert_eq!(game.board().turn(), Side::Red);
+    }
+
+    #[test]
+    fn a_new_move_after_undo_clears_redo() {
+        let mut game = Game::default();
+        play(&mut game, "9-13");
+        play(&mut game, "22-18");
+        assert!(game.undo());
+        assert!(game.can_redo());
+
+        play(&mut game, "21-17");
+        assert!(!game.can_redo());
+        assert!(!game.redo());
+        assert_eq!(game.ply(), 2);
+    }
+}
@@ -0,0 +1,16 @@
+mod bitboard;
+mod board;
+mod game;
+mod moves;
+mod piece;
+mod position;
+mod variant;
+mod zobrist;
+
+pub use bitboard::BitBoard;
+pub use board::Board;
+pub use game::{DrawReason, DrawRules, Game, Outcome};
+pub use moves::{Move, MoveError, Selection};
+pub use piece::{Piece, Rank, Side};
+pub use position::{Direction, Position, BOARD_LENGTH, BOARD_SIZE};
+pub use variant::{variant_name, variant_rules, CaptureRule, Rules, VARIANTS};
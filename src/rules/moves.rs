@@ -0,0 +1,96 @@
+use crate::rules::{Piece, Position};
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Move {
+    pub from: Position,
+    /// Every square the piece lands on, a single one unless it is a capture chain.
+    pub path: Vec<Position>,
+    /// The squares jumped over and what stood on them.
+    pub captured: Vec<(Position, Piece)>,
+    pub promotes: bool,
+}
+
+impl Move {
+    pub fn new(
+        from: Position,
+        path: Vec<Position>,
+        captured: Vec<(Position, Piece)>,
+        piece: Piece,
+    ) -> Move {
+        let to = path[path.len() - 1];
+        Move {
+            from,
+            path,
+            captured,
+            promotes: !piece.is_king() && to.row() == piece.side.promotion_row(),
+        }
+    }
+
+    pub fn to(&self) -> Position {
+        self.path[self.path.len() - 1]
+    }
+
+    pub fn is_capture(&self) -> bool {
+        !self.captured.is_empty()
+    }
+
+    pub fn capture_count(&self) -> usize {
+        self.captured.len()
+    }
+}
+
+/// How far a move picked square by square has come.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Selection {
+    Complete(Move),
+    /// The squares so far start a capture chain that goes on.
+    Partial,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MoveError {
+    EmptySource,
+    NotYourTurn,
+    Occupied,
+    NotDiagonal,
+    Backwards,
+    NothingToCapture,
+    TooFar,
+    CaptureRequired,
+    MajorityCaptureRequired,
+    BrokenChain,
+}
+
+impl MoveError {
+    pub const ALL: [MoveError; 10] = [
+        MoveError::EmptySource,
+        MoveError::NotYourTurn,
+        MoveError::Occupied,
+        MoveError::NotDiagonal,
+        MoveError::Backwards,
+        MoveError::NothingToCapture,
+        MoveError::TooFar,
+        MoveError::CaptureRequired,
+        MoveError::MajorityCaptureRequired,
+        MoveError::BrokenChain,
+    ];
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            MoveError::EmptySource => "There is no piece on that square",
+            MoveError::NotYourTurn => "That piece belongs to the other player",
+            MoveError::Occupied => "The target square is occupied",
+            MoveError::NotDiagonal => "Pieces only move diagonally",
+            MoveError::Backwards => "Only kings may move backwards",
+            MoveError::NothingToCapture => "There is no enemy piece to jump over",
+            MoveError::TooFar => "That square is too far away",
+            MoveError::CaptureRequired => "A capture is available and must be played",
+            MoveError::MajorityCaptureRequired => "You must capture the most pieces possible",
+            MoveError::BrokenChain => "The capturing piece must keep jumping",
+        };
+        write!(f, "{}", reason)
+    }
+}
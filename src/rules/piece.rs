@@ -0,0 +1,84 @@
+use crate::rules::{Direction, BOARD_LENGTH};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Side {
+    Green,
+    Red,
+}
+
+impl Side {
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Green => Side::Red,
+            Side::Red => Side::Green,
+        }
+    }
+
+    /// Row offset of a forward step. Green starts at the top of the board.
+    pub fn forward(self) -> i32 {
+        match self {
+            Side::Green => 1,
+            Side::Red => -1,
+        }
+    }
+
+    pub fn promotion_row(self) -> usize {
+        match self {
+            Side::Green => BOARD_LENGTH - 1,
+            Side::Red => 0,
+        }
+    }
+
+    pub fn is_green(self) -> bool {
+        self == Side::Green
+    }
+
+    pub fn is_red(self) -> bool {
+        self == Side::Red
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Rank {
+    Man,
+    King,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Piece {
+    pub side: Side,
+    pub rank: Rank,
+}
+
+impl Piece {
+    pub fn man(side: Side) -> Piece {
+        Piece {
+            side,
+            rank: Rank::Man,
+        }
+    }
+
+    pub fn king(side: Side) -> Piece {
+        Piece {
+            side,
+            rank: Rank::King,
+        }
+    }
+
+    pub fn is_king(&self) -> bool {
+        self.rank == Rank::King
+    }
+
+    pub fn crowned(&self) -> Piece {
+        Piece::king(self.side)
+    }
+
+    /// The directions this piece is allowed to move and capture in.
+    pub fn directions(&self) -> &'static [Direction] {
+        match (self.rank, self.side) {
+            (Rank::King, _) => &Direction::ALL,
+            (Rank::Man, Side::Green) => &[Direction::DownLeft, Direction::DownRight],
+            (Rank::Man, Side::Red) => &[Direction::UpLeft, Direction::UpRight],
+        }
+    }
+}
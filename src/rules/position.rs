@@ -0,0 +1,108 @@
+pub const BOARD_LENGTH: usize = 8;
+pub const BOARD_SIZE: usize = BOARD_LENGTH * BOARD_LENGTH;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Direction {
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    /// Row and column offset of a single step in this direction.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (-1, 1),
+            Direction::DownLeft => (1, -1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
+    pub fn is_up(self) -> bool {
+        matches!(self, Direction::UpLeft | Direction::UpRight)
+    }
+}
+
+/// A square on the board, row 0 being the top row as drawn on screen.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Position {
+    row: usize,
+    column: usize,
+}
+
+impl Position {
+    pub fn new(row: usize, column: usize) -> Option<Position> {
+        if row < BOARD_LENGTH && column < BOARD_LENGTH {
+            Some(Position { row, column })
+        } else {
+            None
+        }
+    }
+
+    pub fn from_index(index: usize) -> Option<Position> {
+        Position::new(index / BOARD_LENGTH, index % BOARD_LENGTH)
+    }
+
+    pub fn index(&self) -> usize {
+        self.row * BOARD_LENGTH + self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Only these squares can ever hold a piece.
+    pub fn is_playable(&self) -> bool {
+        self.row % 2 == self.column % 2
+    }
+
+    /// The playable square with the standard draughts number `square`, 1 to 32. Green's
+    /// pieces start on 1 to 12, counting right to left from the top row.
+    pub fn from_square(square: usize) -> Option<Position> {
+        if !(1..=BOARD_SIZE / 2).contains(&square) {
+            return None;
+        }
+
+        let row = (square - 1) / (BOARD_LENGTH / 2);
+        let from_right = (square - 1) % (BOARD_LENGTH / 2);
+        let column = BOARD_LENGTH - 2 - from_right * 2 + row % 2;
+        Position::new(row, column)
+    }
+
+    /// The standard number of this square, if it is playable.
+    pub fn square(&self) -> Option<usize> {
+        if self.is_playable() {
+            let from_right = (BOARD_LENGTH - 1 - self.column) / 2;
+            Some(self.row * (BOARD_LENGTH / 2) + from_right + 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn offset(&self, rows: i32, columns: i32) -> Option<Position> {
+        let row = self.row as i32 + rows;
+        let column = self.column as i32 + columns;
+        if row < 0 || column < 0 {
+            return None;
+        }
+        Position::new(row as usize, column as usize)
+    }
+
+    pub fn step(&self, direction: Direction, distance: i32) -> Option<Position> {
+        let (rows, columns) = direction.delta();
+        self.offset(rows * distance, columns * distance)
+    }
+}
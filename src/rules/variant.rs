@@ -0,0 +1,42 @@
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CaptureRule {
+    /// A capture must be played whenever one is available.
+    Mandatory,
+    /// As mandatory, and among captures the one taking the most pieces must be played,
+    /// as in international draughts.
+    Majority,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rules {
+    pub capture: CaptureRule,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            capture: CaptureRule::Mandatory,
+        }
+    }
+}
+
+/// The names the rules go by in the Hub protocol and network games, with the rules they
+/// stand for.
+pub const VARIANTS: [(&str, CaptureRule); 2] = [
+    ("normal", CaptureRule::Mandatory),
+    ("majority", CaptureRule::Majority),
+];
+
+pub fn variant_name(rules: Rules) -> &'static str {
+    VARIANTS
+        .iter()
+        .find(|(_, capture)| *capture == rules.capture)
+        .map_or("normal", |(name, _)| name)
+}
+
+pub fn variant_rules(name: &str) -> Option<Rules> {
+    VARIANTS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, capture)| Rules { capture: *capture })
+}
@@ -0,0 +1,81 @@
+use crate::rules::{Piece, Position, Rank, Side, BOARD_SIZE};
+
+// fixed seed, so a position has the same key in every run and on every machine
+const SEED: u64 = 0x2f6b_1d3a_95c4_e807;
+
+const PIECE_KEYS: [[u64; BOARD_SIZE]; 4] = piece_keys();
+
+/// Mixed into the key while red is to move.
+pub const TURN_KEY: u64 = split_mix(SEED);
+
+/// The Zobrist key of `piece` standing on `pos`.
+pub fn piece_key(pos: Position, piece: Piece) -> u64 {
+    let kind = match (piece.side, piece.rank) {
+        (Side::Green, Rank::Man) => 0,
+        (Side::Green, Rank::King) => 1,
+        (Side::Red, Rank::Man) => 2,
+        (Side::Red, Rank::King) => 3,
+    };
+    PIECE_KEYS[kind][pos.index()]
+}
+
+const fn split_mix(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn piece_keys() -> [[u64; BOARD_SIZE]; 4] {
+    let mut keys = [[0; BOARD_SIZE]; 4];
+    let mut state = split_mix(SEED);
+    let mut kind = 0;
+    while kind < 4 {
+        let mut index = 0;
+        while index < BOARD_SIZE {
+            state = split_mix(state);
+            keys[kind][index] = state;
+            index += 1;
+        }
+        kind += 1;
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Random;
+    use crate::rules::Board;
+
+    fn from_scratch(board: &Board) -> u64 {
+        let turn = if board.turn().is_red() { TURN_KEY } else { 0 };
+        board
+            .pieces()
+            .fold(turn, |key, (pos, piece)| key ^ piece_key(pos, piece))
+    }
+
+    #[test]
+    fn the_kept_key_matches_one_from_scratch() {
+        let mut random = Random::new(7);
+        for _ in 0..50 {
+            let mut board = Board::new();
+            let mut played = vec![];
+            loop {
+                assert_eq!(board.key(), from_scratch(&board));
+                let moves = board.legal_moves();
+                if moves.is_empty() || played.len() == 150 {
+                    break;
+                }
+                let mv = moves[random.below(moves.len())].clone();
+                board.apply(&mv);
+                played.push(mv);
+            }
+            while let Some(mv) = played.pop() {
+                board.unapply(&mv);
+                assert_eq!(board.key(), from_scratch(&board));
+            }
+            assert_eq!(board.key(), Board::new().key());
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::rules::{BitBoard, Rules};
+use crate::tablebase::{Material, Tablebase, Value};
+
+// the most plies a result can be away, so that it still fits a byte
+const MAX_DISTANCE: usize = 254;
+
+/// What is known about one position while its material is being solved.
+#[derive(Clone)]
+struct Solving {
+    value: Option<Value>,
+    /// Moves staying within the material that have not been found to be won by the opponent.
+    open_moves: u8,
+    /// The longest win found for the opponent so far.
+    longest: u8,
+    /// A move to a draw or to a position lost for the opponent, so this one can not be lost.
+    escapes: bool,
+}
+
+/// Solves every material up to `pieces` pieces, calling `progress` after each one.
+pub fn generate<F: FnMut(Material, &[u8])>(
+    pieces: usize,
+    rules: Rules,
+    mut progress: F,
+) -> Result<Tablebase, String> {
+    let mut tablebase = Tablebase::new(pieces, rules);
+    for material in Material::up_to(pieces) {
+        let values = solve(&tablebase, material)?;
+        progress(material, &values);
+        tablebase.insert(material, values);
+    }
+    Ok(tablebase)
+}
+
+// retrograde analysis: results spread backwards from the end of the game, one ply at a time,
+// so the first result reaching a position is also the fastest one
+fn solve(tablebase: &Tablebase, material: Material) -> Result<Vec<u8>, String> {
+    let rules = tablebase.rules();
+    let size = material.size();
+    let mut positions = vec![
+        Solving {
+            value: None,
+            open_moves: 0,
+            longest: 0,
+            escapes: false,
+        };
+        size
+    ];
+    // positions waiting to be settled, by the number of plies to the end of the game
+    let mut pending: Vec<Vec<(usize, Value)>> = vec![vec![]; MAX_DISTANCE + 2];
+
+    for (index, solving) in positions.iter_mut().enumerate() {
+        let mut board = match material.board(index, rules) {
+            Some(board) => board,
+            None => {
+                // two pieces on one square, not a position
+                solving.value = Some(Value::Draw);
+                continue;
+            }
+        };
+
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            pending[0].push((index, Value::Loss(0)));
+            continue;
+        }
+
+        // captures and crownings lead to materials already solved
+        let mut fastest_win = None;
+        for mv in &moves {
+            if !mv.is_capture() && !mv.promotes {
+                solving.open_moves += 1;
+                continue;
+            }
+
+            board.apply(mv);
+            let value = tablebase
+                .probe(&board)
+                .ok_or_else(|| format!("Material {:?} was not solved first", material))?;
+            board.unapply(mv);
+
+            match value {
+                Value::Loss(plies) => {
+                    solving.escapes = true;
+                    fastest_win = Some(fastest_win.map_or(plies, |win: u8| win.min(plies)));
+                }
+                Value::Draw => solving.escapes = true,
+                Value::Win(plies) => solving.longest = solving.longest.max(plies),
+            }
+        }
+
+        if let Some(plies) = fastest_win {
+            let plies = plies as usize + 1;
+            pending[plies].push((index, Value::Win(plies as u8)));
+        } else if solving.open_moves == 0 && !solving.escapes {
+            let plies = solving.longest as usize + 1;
+            pending[plies].push((index, Value::Loss(plies as u8)));
+        }
+    }
+
+    for plies in 0..=MAX_DISTANCE {
+        let settled = std::mem::take(&mut pending[plies]);
+        for (index, value) in settled {
+            if positions[index].value.is_some() {
+                continue;
+            }
+            positions[index].value = Some(value);
+
+            let board = material.board(index, rules).unwrap();
+            for before in predecessors(&board) {
+                let before = material.index(&before);
+                let solving = &mut positions[before];
+                if solving.value.is_some() {
+                    continue;
+                }
+
+                match value {
+                    // the move here wins for the side that makes it
+                    Value::Loss(_) => {
+                        pending[plies + 1].push((before, Value::Win(plies as u8 + 1)))
+                    }
+                    Value::Win(_) => {
+                        solving.open_moves -= 1;
+                        solving.longest = solving.longest.max(plies as u8);
+                        if solving.open_moves == 0 && !solving.escapes {
+                            let loss = solving.longest as usize + 1;
+                            if loss > MAX_DISTANCE {
+                                return Err(format!("Material {:?} has too long wins", material));
+                            }
+                            pending[loss].push((before, Value::Loss(loss as u8)));
+                        }
+                    }
+                    Value::Draw => {}
+                }
+            }
+        }
+    }
+
+    let is_cut_short = pending[MAX_DISTANCE + 1]
+        .iter()
+        .any(|(index, _)| positions[*index].value.is_none());
+    if is_cut_short {
+        return Err(format!("Material {:?} has too long wins", material));
+    }
+
+    // whatever was never settled can be held forever by both sides
+    Ok(positions
+        .iter()
+        .map(|solving| Value::encode(solving.value.unwrap_or(Value::Draw)))
+        .collect())
+}
+
+/// The positions of the same material that reach `board` with a plain move: no capture and
+/// no crowning, as those change the material.
+fn predecessors(board: &BitBoard) -> Vec<BitBoard> {
+    let mover = board.turn().opponent();
+    let mut before = vec![];
+
+    for (pos, piece) in board.pieces() {
+        if piece.side != mover {
+            continue;
+        }
+        for direction in piece.directions() {
+            let from = match pos.step(*direction, -1) {
+                Some(from) if board.piece_at(from).is_none() => from,
+                _ => continue,
+            };
+
+            let mut previous = *board;
+            previous.set_piece(pos, None);
+            previous.set_piece(from, Some(piece));
+            previous.set_turn(mover);
+
+            // a plain move is only legal while there is nothing to capture
+            let can_capture = previous
+                .legal_moves()
+                .first()
+                .is_some_and(|mv| mv.is_capture());
+            if !can_capture {
+                before.push(previous);
+            }
+        }
+    }
+    before
+}
@@ -0,0 +1,215 @@
+use crate::rules::{BitBoard, Piece, Position, Rank, Rules, Side, BOARD_SIZE};
+
+const SQUARES: usize = BOARD_SIZE / 2;
+// men never stand on the row they would be crowned on
+const MAN_SQUARES: usize = SQUARES - 4;
+
+const fn binomials() -> [[u64; SQUARES + 1]; SQUARES + 1] {
+    let mut table = [[0; SQUARES + 1]; SQUARES + 1];
+    let mut n = 0;
+    while n <= SQUARES {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k <= n {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+const BINOMIALS: [[u64; SQUARES + 1]; SQUARES + 1] = binomials();
+
+fn choose(n: usize, k: usize) -> u64 {
+    if k > n {
+        0
+    } else {
+        BINOMIALS[n][k]
+    }
+}
+
+/// How many pieces of each kind there are, which decides the part of the tablebase a position
+/// is stored in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Material {
+    pub green_men: u8,
+    pub green_kings: u8,
+    pub red_men: u8,
+    pub red_kings: u8,
+}
+
+impl Material {
+    pub fn of(board: &BitBoard) -> Material {
+        let count = |side| {
+            let kings = board.kings(side);
+            ((board.count(side) - kings) as u8, kings as u8)
+        };
+        let (green_men, green_kings) = count(Side::Green);
+        let (red_men, red_kings) = count(Side::Red);
+        Material {
+            green_men,
+            green_kings,
+            red_men,
+            red_kings,
+        }
+    }
+
+    pub fn pieces(&self) -> usize {
+        (self.green_men + self.green_kings + self.red_men + self.red_kings) as usize
+    }
+
+    pub fn men(&self) -> usize {
+        (self.green_men + self.red_men) as usize
+    }
+
+    /// Every material with both sides on the board and at most `pieces` pieces, in an order
+    /// where captures and crownings only lead to materials that come earlier.
+    pub fn up_to(pieces: usize) -> Vec<Material> {
+        let mut all = vec![];
+        let most = pieces.min(SQUARES) as u8;
+        for green_men in 0..=most {
+            for green_kings in 0..=most - green_men {
+                for red_men in 0..=most - green_men - green_kings {
+                    for red_kings in 0..=most - green_men - green_kings - red_men {
+                        let material = Material {
+                            green_men,
+                            green_kings,
+                            red_men,
+                            red_kings,
+                        };
+                        if green_men + green_kings > 0 && red_men + red_kings > 0 {
+                            all.push(material);
+                        }
+                    }
+                }
+            }
+        }
+        all.sort_by_key(|material| (material.pieces(), material.men(), *material));
+        all
+    }
+
+    // the piece groups in the order they make up an index, with the squares they may use
+    fn groups(&self) -> [(Piece, usize, usize); 4] {
+        [
+            (
+                Piece::man(Side::Green),
+                self.green_men as usize,
+                MAN_SQUARES,
+            ),
+            (Piece::king(Side::Green), self.green_kings as usize, SQUARES),
+            (Piece::man(Side::Red), self.red_men as usize, MAN_SQUARES),
+            (Piece::king(Side::Red), self.red_kings as usize, SQUARES),
+        ]
+    }
+
+    /// Number of indices, including some that do not stand for a position because pieces of
+    /// different groups would share a square.
+    pub fn size(&self) -> usize {
+        let positions: u64 = self
+            .groups()
+            .iter()
+            .map(|(_, count, squares)| choose(*squares, *count))
+            .product();
+        (positions * 2) as usize
+    }
+
+    /// The index of `board`, which must have exactly this material.
+    pub fn index(&self, board: &BitBoard) -> usize {
+        let mut index = match board.turn() {
+            Side::Green => 0,
+            Side::Red => 1,
+        };
+
+        for (piece, count, squares) in self.groups().iter() {
+            let mut places: Vec<usize> = board
+                .pieces()
+                .filter(|(_, other)| other == piece)
+                .map(|(pos, _)| place_of(pos, *piece))
+                .collect();
+            places.sort_unstable();
+
+            let rank: u64 = places
+                .iter()
+                .enumerate()
+                .map(|(i, place)| choose(*place, i + 1))
+                .sum();
+            index = index * choose(*squares, *count) as usize + rank as usize;
+        }
+        index
+    }
+
+    /// The position at `index`, `None` when pieces would share a square.
+    pub fn board(&self, index: usize, rules: Rules) -> Option<BitBoard> {
+        let mut rest = index;
+        let mut ranks = [0; 4];
+        let groups = self.groups();
+        for (i, (_, count, squares)) in groups.iter().enumerate().rev() {
+            let size = choose(*squares, *count) as usize;
+            ranks[i] = rest % size;
+            rest /= size;
+        }
+        let turn = if rest == 0 { Side::Green } else { Side::Red };
+
+        let mut board = BitBoard::empty(turn).with_rules(rules);
+        let mut taken = 0u32;
+        for ((piece, count, squares), rank) in groups.iter().zip(ranks.iter()) {
+            let mut rank = *rank as u64;
+            for k in (1..=*count).rev() {
+                // the highest place whose binomial still fits in what is left of the rank
+                let mut place = k - 1;
+                while place + 1 < *squares && choose(place + 1, k) <= rank {
+                    place += 1;
+                }
+                rank -= choose(place, k);
+
+                let pos = position_of(place, *piece);
+                let bit = 1 << (pos.square().unwrap() - 1);
+                if taken & bit != 0 {
+                    return None;
+                }
+                taken |= bit;
+                board.set_piece(pos, Some(*piece));
+            }
+        }
+        Some(board)
+    }
+}
+
+// squares numbered from 0 among those the piece may stand on
+fn place_of(pos: Position, piece: Piece) -> usize {
+    let square = pos.square().unwrap() - 1;
+    match (piece.rank, piece.side) {
+        (Rank::Man, Side::Red) => square - (SQUARES - MAN_SQUARES),
+        _ => square,
+    }
+}
+
+fn position_of(place: usize, piece: Piece) -> Position {
+    let square = match (piece.rank, piece.side) {
+        (Rank::Man, Side::Red) => place + (SQUARES - MAN_SQUARES),
+        _ => place,
+    };
+    Position::from_square(square + 1).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_position_has_its_own_index() {
+        let rules = Rules::default();
+        for material in Material::up_to(3) {
+            let mut positions = 0;
+            for index in 0..material.size() {
+                if let Some(board) = material.board(index, rules) {
+                    assert_eq!(Material::of(&board), material);
+                    assert_eq!(material.index(&board), index, "{:?}", material);
+                    positions += 1;
+                }
+            }
+            assert!(positions > 0, "{:?}", material);
+        }
+    }
+}
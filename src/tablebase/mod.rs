@@ -0,0 +1,356 @@
+//! Endgame databases with the perfect result of every position with few pieces left.
+
+mod generate;
+mod index;
+
+pub use generate::generate;
+pub use index::Material;
+
+use crate::rules::{BitBoard, CaptureRule, Move, Rules};
+use std::collections::HashMap;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"RCTB";
+const VERSION: u8 = 1;
+// shorter repeats are cheaper to copy as they are
+const MIN_RUN: usize = 4;
+
+/// The result of a position with perfect play, seen from the side to move, with the number of
+/// plies until the game is over.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Value {
+    Win(u8),
+    Loss(u8),
+    Draw,
+}
+
+impl Value {
+    // a whole position in one byte: zero for a draw, otherwise the plies to the end plus one,
+    // with odd distances being wins for the side to move
+    fn encode(self) -> u8 {
+        match self {
+            Value::Win(plies) | Value::Loss(plies) => plies + 1,
+            Value::Draw => 0,
+        }
+    }
+
+    fn decode(byte: u8) -> Value {
+        match byte {
+            0 => Value::Draw,
+            byte if byte % 2 == 0 => Value::Win(byte - 1),
+            byte => Value::Loss(byte - 1),
+        }
+    }
+
+    /// The value for the side that made the move leading to this position.
+    pub fn before(self) -> Value {
+        match self {
+            Value::Win(plies) => Value::Loss(plies.saturating_add(1)),
+            Value::Loss(plies) => Value::Win(plies.saturating_add(1)),
+            Value::Draw => Value::Draw,
+        }
+    }
+}
+
+pub struct Tablebase {
+    pieces: usize,
+    rules: Rules,
+    materials: HashMap<Material, Vec<u8>>,
+}
+
+impl Tablebase {
+    fn new(pieces: usize, rules: Rules) -> Tablebase {
+        Tablebase {
+            pieces,
+            rules,
+            materials: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, material: Material, values: Vec<u8>) {
+        self.materials.insert(material, values);
+    }
+
+    /// Positions with at most this many pieces can be probed.
+    pub fn pieces(&self) -> usize {
+        self.pieces
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// The perfect result of `board`, `None` when it has too many pieces or other rules.
+    pub fn probe(&self, board: &BitBoard) -> Option<Value> {
+        if board.rules() != self.rules {
+            return None;
+        }
+        if board.count(board.turn()) == 0 {
+            return Some(Value::Loss(0));
+        }
+
+        let material = Material::of(board);
+        if material.pieces() > self.pieces {
+            return None;
+        }
+        let values = self.materials.get(&material)?;
+        Some(Value::decode(values[material.index(board)]))
+    }
+
+    /// The move keeping the best result: the fastest win, a draw, or else the slowest loss.
+    pub fn best_move(&self, board: &BitBoard) -> Option<(Move, Value)> {
+        let mut best: Option<(Move, Value)> = None;
+        let mut board = *board;
+        for mv in board.legal_moves() {
+            board.apply(&mv);
+            let value = self.probe(&board)?.before();
+            board.unapply(&mv);
+
+            let is_better = match &best {
+                Some((_, known)) => rank(value) > rank(*known),
+                None => true,
+            };
+            if is_better {
+                best = Some((mv, value));
+            }
+        }
+        best
+    }
+
+    /// Writes the tablebase with every material's values compressed.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(match self.rules.capture {
+            CaptureRule::Mandatory => 0,
+            CaptureRule::Majority => 1,
+        });
+        bytes.push(self.pieces as u8);
+        write_number(&mut bytes, self.materials.len() as u64);
+
+        let mut materials: Vec<_> = self.materials.iter().collect();
+        materials.sort_by_key(|(material, _)| **material);
+        for (material, values) in materials {
+            bytes.extend_from_slice(&[
+                material.green_men,
+                material.green_kings,
+                material.red_men,
+                material.red_kings,
+            ]);
+
+            let compressed = compress(values);
+            write_number(&mut bytes, compressed.len() as u64);
+            bytes.extend_from_slice(&compressed);
+        }
+
+        std::fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Tablebase, String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let mut reader = Reader { bytes: &bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC || reader.byte()? != VERSION {
+            return Err(format!("{} is not a tablebase", path.display()));
+        }
+        let capture = match reader.byte()? {
+            0 => CaptureRule::Mandatory,
+            1 => CaptureRule::Majority,
+            _ => return Err(String::from("Unknown capture rule in the tablebase")),
+        };
+        let mut tablebase = Tablebase::new(reader.byte()? as usize, Rules { capture });
+
+        for _ in 0..reader.number()? {
+            let counts = reader.take(4)?;
+            let material = Material {
+                green_men: counts[0],
+                green_kings: counts[1],
+                red_men: counts[2],
+                red_kings: counts[3],
+            };
+
+            let length = reader.number()? as usize;
+            let mut compressed = Reader {
+                bytes: reader.take(length)?,
+            };
+            let values = decompress(&mut compressed, material.size())?;
+
+            if values.len() != material.size() {
+                return Err(format!("Material {:?} is damaged", material));
+            }
+            tablebase.insert(material, values);
+        }
+        Ok(tablebase)
+    }
+}
+
+// wins beat draws beat losses, faster wins and slower losses first
+fn rank(value: Value) -> i32 {
+    match value {
+        Value::Win(plies) => 1000 - plies as i32,
+        Value::Draw => 0,
+        Value::Loss(plies) => -1000 + plies as i32,
+    }
+}
+
+// a repeated value is stored once with how often it repeats, everything in between is copied
+// as it is. The length comes first, with the lowest bit telling which of the two follows
+fn compress(values: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut literal_start = 0;
+    let mut at = 0;
+
+    while at < values.len() {
+        let length = values[at..]
+            .iter()
+            .take_while(|value| **value == values[at])
+            .count();
+        if length < MIN_RUN {
+            at += length;
+            continue;
+        }
+
+        if literal_start < at {
+            write_number(&mut bytes, ((at - literal_start) as u64) << 1 | 1);
+            bytes.extend_from_slice(&values[literal_start..at]);
+        }
+        write_number(&mut bytes, (length as u64) << 1);
+        bytes.push(values[at]);
+        at += length;
+        literal_start = at;
+    }
+
+    if literal_start < values.len() {
+        write_number(&mut bytes, ((values.len() - literal_start) as u64) << 1 | 1);
+        bytes.extend_from_slice(&values[literal_start..]);
+    }
+    bytes
+}
+
+fn decompress(reader: &mut Reader, size: usize) -> Result<Vec<u8>, String> {
+    let mut values = Vec::with_capacity(size);
+    while !reader.bytes.is_empty() {
+        let header = reader.number()?;
+        let length = (header >> 1) as usize;
+        if values.len() + length > size {
+            return Err(String::from("The tablebase holds too many values"));
+        }
+        if header & 1 == 1 {
+            values.extend_from_slice(reader.take(length)?);
+        } else {
+            let value = reader.byte()?;
+            values.resize(values.len() + length, value);
+        }
+    }
+    Ok(values)
+}
+
+// seven bits at a time, the high bit set while more follow
+fn write_number(bytes: &mut Vec<u8>, mut number: u64) {
+    while number >= 0x80 {
+        bytes.push(number as u8 | 0x80);
+        number >>= 7;
+    }
+    bytes.push(number as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < length {
+            return Err(String::from("The tablebase ends too soon"));
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn number(&mut self) -> Result<u64, String> {
+        let mut number = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            number |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+        }
+        Err(String::from(
+            "The tablebase holds a number that is too large",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_fen;
+
+    fn probe(tablebase: &Tablebase, fen: &str) -> Option<Value> {
+        tablebase.probe(&BitBoard::from(&parse_fen(fen).unwrap()))
+    }
+
+    #[test]
+    fn values_survive_compression() {
+        let mut long_run = vec![3; 1000];
+        long_run.extend([1, 2, 1, 2, 5, 5, 5, 5, 5, 0]);
+        let cases = [vec![], vec![7], vec![1, 2, 3], vec![4, 4, 4, 4], long_run];
+        for values in cases {
+            let compressed = compress(&values);
+            let mut reader = Reader { bytes: &compressed };
+            assert_eq!(decompress(&mut reader, values.len()).unwrap(), values);
+        }
+        assert!(compress(&[9; 1000]).len() < 4);
+    }
+
+    #[test]
+    fn numbers_survive_writing() {
+        for number in [0, 1, 127, 128, 300, 16_384, u32::MAX as u64, u64::MAX] {
+            let mut bytes = vec![];
+            write_number(&mut bytes, number);
+            let mut reader = Reader { bytes: &bytes };
+            assert_eq!(reader.number().unwrap(), number);
+            assert!(reader.bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn solves_small_endings() {
+        let tablebase = generate(3, Rules::default(), |_, _| {}).unwrap();
+        assert_eq!(probe(&tablebase, "B:WK32:BK1"), Some(Value::Draw));
+
+        // two kings catch the lone one in the corner, which takes a while but not forever
+        match probe(&tablebase, "B:WK32:BK1,K2") {
+            Some(Value::Win(plies)) => assert!(plies % 2 == 1 && plies < 60, "{}", plies),
+            value => panic!("expected a win, got {:?}", value),
+        }
+        assert!(matches!(
+            probe(&tablebase, "W:WK32:BK1,K2"),
+            Some(Value::Loss(_))
+        ));
+        assert_eq!(probe(&tablebase, "B:WK32,K31:BK1,K2"), None);
+
+        let path = std::env::temp_dir().join(format!("tablebase-{}.bin", std::process::id()));
+        tablebase.save(&path).unwrap();
+        let loaded = Tablebase::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.pieces(), 3);
+        assert_eq!(loaded.materials, tablebase.materials);
+    }
+
+    #[test]
+    fn refuses_other_files() {
+        let path = std::env::temp_dir().join(format!("not-a-tablebase-{}.bin", std::process::id()));
+        std::fs::write(&path, b"RCTB").unwrap();
+        let loaded = Tablebase::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use checkers::dxp::{Connection, DxpGame, EndReason, GameSettings, Message, VERSION};
+use checkers::engine::{Searcher, Worker};
+use checkers::rules::{Board, Game, Outcome, Side};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const DEPTH: u32 = 4;
+
+// the follower's game and the outcome it saw
+type Played = Result<(Game, Option<Outcome>), String>;
+
+fn engine() -> Worker {
+    Worker::new(Searcher::new(DEPTH))
+}
+
+// a follower playing a whole game on its own thread, with the stream for the initiator
+fn follower() -> (TcpStream, JoinHandle<Played>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let follower = thread::spawn(move || {
+        let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+        let mut dxp = DxpGame::follow(stream, "follower")?;
+        let outcome = dxp.play(&mut engine())?;
+        Ok((dxp.game().clone(), outcome))
+    });
+    (TcpStream::connect(address).unwrap(), follower)
+}
+
+fn moves(game: &Game) -> Vec<String> {
+    game.moves().map(|mv| format!("{:?}", mv)).collect()
+}
+
+#[test]
+fn messages_survive_a_round_trip() {
+    let mut board = Board::empty(Side::Red);
+    for (pos, piece) in Board::new().pieces().take(5) {
+        board.set_piece(pos, Some(piece.crowned()));
+    }
+
+    let messages = vec![
+        Message::GameRequest {
+            version: VERSION,
+            name: String::from("initiator"),
+            follower: Side::Red,
+            minutes: 10,
+            moves: 50,
+            start: None,
+        },
+        Message::GameRequest {
+            version: VERSION,
+            name: String::from("initiator"),
+            follower: Side::Green,
+            minutes: 0,
+            moves: 0,
+            start: Some(board),
+        },
+        Message::GameAccept {
+            name: String::from("follower"),
+            code: 0,
+        },
+        Message::Move {
+            seconds: 12,
+            from: 9,
+            to: 27,
+            captured: vec![14, 23],
+        },
+        Message::GameEnd {
+            reason: EndReason::Draw,
+            stop: true,
+        },
+        Message::Chat(String::from("good game")),
+        Message::BackRequest {
+            number: 12,
+            turn: Side::Red,
+        },
+        Message::BackAccept { code: 2 },
+    ];
+    for message in messages {
+        assert_eq!(Message::parse(&message.to_string()).unwrap(), message);
+    }
+}
+
+#[test]
+fn game_requests_have_fixed_width_fields() {
+    let message = Message::GameRequest {
+        version: 1,
+        name: String::from("me"),
+        follower: Side::Red,
+        minutes: 5,
+        moves: 75,
+        start: None,
+    };
+    assert_eq!(message.to_string(), format!("R01{:<32}W005075A", "me"));
+    assert!(Message::parse("R01short").is_err());
+}
+
+#[test]
+fn two_engines_play_a_game_to_the_end() {
+    let (stream, follower) = follower();
+    let mut dxp = DxpGame::initiate(stream, "initiator", &GameSettings::default()).unwrap();
+    assert_eq!(dxp.side(), Side::Green);
+    assert_eq!(dxp.peer_name(), "follower");
+
+    let outcome = dxp.play(&mut engine()).unwrap();
+    let (game, follower_outcome) = follower.join().unwrap().unwrap();
+
+    assert!(outcome.is_some());
+    assert_eq!(outcome, follower_outcome);
+    assert_eq!(moves(dxp.game()), moves(&game));
+}
+
+#[test]
+fn the_follower_plays_from_the_requested_position() {
+    let mut board = Board::empty(Side::Red);
+    for (pos, piece) in Board::new().pieces().filter(|(pos, _)| pos.row() % 7 == 0) {
+        board.set_piece(pos, Some(piece));
+    }
+    let settings = GameSettings {
+        side: Side::Red,
+        start: Some(board),
+        ..GameSettings::default()
+    };
+
+    let (stream, follower) = follower();
+    let mut dxp = DxpGame::initiate(stream, "initiator", &settings).unwrap();
+    let outcome = dxp.play(&mut engine()).unwrap();
+    let (game, follower_outcome) = follower.join().unwrap().unwrap();
+
+    assert_eq!(*game.start(), board);
+    assert_eq!(outcome, follower_outcome);
+    assert_eq!(moves(dxp.game()), moves(&game));
+}
+
+#[test]
+fn moves_can_be_taken_back() {
+    let (stream, follower) = follower();
+    let mut dxp = DxpGame::initiate(stream, "initiator", &GameSettings::default()).unwrap();
+    let mut worker = engine();
+
+    for _ in 0..4 {
+        assert!(dxp.play_ply(&mut worker).unwrap());
+    }
+    assert_eq!(dxp.game().ply(), 4);
+    assert!(dxp.take_back(2).unwrap());
+    assert_eq!(dxp.game().ply(), 2);
+
+    let outcome = dxp.play(&mut worker).unwrap();
+    let (game, follower_outcome) = follower.join().unwrap().unwrap();
+    assert_eq!(outcome, follower_outcome);
+    assert_eq!(moves(dxp.game()), moves(&game));
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let (stream, follower) = follower();
+    let mut connection = Connection::new(stream).unwrap();
+    connection
+        .send(&Message::GameRequest {
+            version: VERSION + 1,
+            name: String::from("future"),
+            follower: Side::Red,
+            minutes: 5,
+            moves: 75,
+            start: None,
+        })
+        .unwrap();
+
+    let answer = connection.receive(Duration::from_secs(5)).unwrap();
+    assert_eq!(
+        answer,
+        Some(Message::GameAccept {
+            name: String::from("follower"),
+            code: 1,
+        })
+    );
+    assert!(follower.join().unwrap().is_err());
+}
@@ -0,0 +1,78 @@
+use checkers::engine::Engine;
+use checkers::hub::{parse_move, parse_position, write_move, write_position, HubEngine, Message};
+use checkers::rules::{Board, CaptureRule, Game, Rules};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn stub() -> &'static Path {
+    Path::new(env!("CARGO_BIN_EXE_hub-stub"))
+}
+
+#[test]
+fn messages_keep_their_arguments() {
+    let message = Message::parse("pos pos=Bbbb moves=\"11-15 23-19\" ponder").unwrap();
+    assert_eq!(message.command, "pos");
+    assert_eq!(message.get("pos"), Some("Bbbb"));
+    assert_eq!(message.get("moves"), Some("11-15 23-19"));
+    assert!(message.has("ponder"));
+    assert_eq!(Message::parse(&message.to_string()).unwrap(), message);
+}
+
+#[test]
+fn positions_and_moves_survive_a_round_trip() {
+    let board = Board::new();
+    assert_eq!(parse_position(&write_position(&board)).unwrap(), board);
+
+    for mv in board.legal_moves() {
+        assert_eq!(parse_move(&board, &write_move(&mv)).unwrap(), mv);
+    }
+}
+
+#[test]
+fn handshake_names_the_engine() {
+    let engine = HubEngine::launch(stub(), Rules::default()).unwrap();
+    assert_eq!(engine.name(), "stub");
+
+    let majority = Rules {
+        capture: CaptureRule::Majority,
+    };
+    assert!(HubEngine::launch(stub(), majority).is_ok());
+}
+
+#[test]
+fn engine_plays_a_whole_game() {
+    let mut engine = HubEngine::launch(stub(), Rules::default()).unwrap();
+    let stop = AtomicBool::new(false);
+    let mut game = Game::default();
+
+    while !game.is_over() && game.ply() < 200 {
+        let result = engine.search(&game, &stop).unwrap().unwrap();
+        assert!(game.board().legal_moves().contains(&result.best));
+        assert_eq!(result.score, 25);
+        game.play(&result.best);
+    }
+    assert!(game.ply() > 0);
+}
+
+#[test]
+fn stopping_ends_the_search() {
+    let mut engine = HubEngine::launch(stub(), Rules::default())
+        .unwrap()
+        .with_move_time(Duration::from_secs(60));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let stopper = stop.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        stopper.store(true, Ordering::Relaxed);
+    });
+
+    let started = Instant::now();
+    // the move the engine answers the stop with is the one played
+    let result = engine.search(&Game::default(), &stop).unwrap().unwrap();
+    assert!(Board::new().legal_moves().contains(&result.best));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
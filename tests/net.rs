@@ -0,0 +1,528 @@
+use checkers::net::{Guest, Host, Link, Message, NetEvent, Peer, Spectator, VERSION};
+use checkers::notation::{parse_move, write_move};
+use checkers::rules::{Board, Game, Move, Outcome, Rules, Side};
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn host(game: Game) -> Host {
+    Host::bind("127.0.0.1:0", Side::Green, game).unwrap()
+}
+
+// the host only answers while it is polled, so joining runs on its own thread meanwhile, the
+// host's events are handed back along with the guest
+fn join(host: &mut Host, name: &str) -> (Result<Guest, String>, Vec<NetEvent>) {
+    let address = host.local_addr().unwrap();
+    let name = String::from(name);
+    let joining = thread::spawn(move || Guest::join(address, &name));
+    let mut events = vec![];
+    while !joining.is_finished() {
+        events.extend(host.poll().unwrap());
+        thread::sleep(Duration::from_millis(1));
+    }
+    (joining.join().unwrap(), events)
+}
+
+fn next_event(link: &mut dyn Link) -> NetEvent {
+    let deadline = Instant::now() + TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(event) = link.poll().unwrap() {
+            return event;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("Nothing happened in time");
+}
+
+fn first_move(game: &Game) -> Move {
+    game.board().legal_moves().remove(0)
+}
+
+// passes connections on to `target` until they are cut, to drop them without either end knowing
+struct Proxy {
+    address: SocketAddr,
+    streams: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl Proxy {
+    fn new(target: SocketAddr) -> Proxy {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let streams = Arc::new(Mutex::new(vec![]));
+        let kept = Arc::clone(&streams);
+        thread::spawn(move || {
+            for client in listener.incoming().map_while(Result::ok) {
+                let server = TcpStream::connect(target).unwrap();
+                let mut kept = kept.lock().unwrap();
+                for (mut from, mut to) in [
+                    (client.try_clone().unwrap(), server.try_clone().unwrap()),
+                    (server.try_clone().unwrap(), client.try_clone().unwrap()),
+                ] {
+                    thread::spawn(move || {
+                        let _ = io::copy(&mut from, &mut to);
+                        let _ = to.shutdown(Shutdown::Both);
+                    });
+                }
+                kept.extend([client, server]);
+            }
+        });
+        Proxy { address, streams }
+    }
+
+    fn cut(&self) {
+        for stream in self.streams.lock().unwrap().drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+#[test]
+fn messages_survive_a_round_trip() {
+    let messages = vec![
+        Message::Hello {
+            version: VERSION,
+            name: String::from("Anne Marie"),
+        },
+        Message::Welcome {
+            version: VERSION,
+            side: Some(Side::Red),
+            session: Some(0x5eed_cafe),
+            green_time: Duration::from_millis(61_500),
+            red_time: Duration::from_millis(2_048),
+            rules: Rules::default(),
+            start: Board::new(),
+            moves: vec![String::from("11-15"), String::from("23-19")],
+        },
+        Message::Watch {
+            version: VERSION,
+            name: String::from("fan"),
+        },
+        Message::Welcome {
+            version: VERSION,
+            side: None,
+            session: None,
+            green_time: Duration::ZERO,
+            red_time: Duration::ZERO,
+            rules: Rules::default(),
+            start: Board::new(),
+            moves: vec![],
+        },
+        Message::Resume {
+            version: VERSION,
+            session: u64::MAX,
+            name: String::from("Anne Marie"),
+        },
+        Message::Move(String::from("9x18x27")),
+        Message::Forfeit(Side::Red),
+        Message::Refused(String::from("It is not your turn")),
+        Message::Chat {
+            name: String::from("Anne Marie"),
+            text: String::from("good luck: you'll need it"),
+        },
+    ];
+    for message in messages {
+        assert_eq!(Message::parse(&message.to_string()).unwrap(), message);
+    }
+    assert!(Message::parse("move").is_err());
+    assert!(Message::parse("welcome 2 blue none 0 0 normal B:W21:B1").is_err());
+    assert!(Message::parse("resume 2 not-hex guest").is_err());
+}
+
+#[test]
+fn a_guest_gets_the_game_so_far() {
+    let mut game = Game::default();
+    for text in ["11-15", "23-19"] {
+        game.play(&parse_move(game.board(), text).unwrap());
+    }
+    let mut host = host(game.clone());
+    assert!(!host.is_connected());
+
+    let (guest, events) = join(&mut host, "guest");
+    let guest = guest.unwrap();
+    assert_eq!(events, vec![NetEvent::Joined(String::from("guest"))]);
+    assert_eq!(guest.side(), Side::Red);
+    assert_eq!(guest.game().start(), game.start());
+    assert_eq!(
+        guest.game().moves().collect::<Vec<&Move>>(),
+        game.moves().collect::<Vec<&Move>>()
+    );
+    assert!(host.is_connected());
+}
+
+#[test]
+fn moves_are_played_once_the_host_has_them() {
+    let mut host = host(Game::default());
+    let mut guest = join(&mut host, "guest").0.unwrap();
+
+    // the guest may not move first, the host may
+    assert!(guest.send_move(&first_move(guest.game())).is_err());
+    let mv = first_move(host.game());
+    host.send_move(&mv).unwrap();
+    assert_eq!(next_event(&mut host), NetEvent::Moved(mv.clone()));
+    assert_eq!(next_event(&mut guest), NetEvent::Moved(mv));
+
+    let reply = first_move(guest.game());
+    guest.send_move(&reply).unwrap();
+    assert_eq!(next_event(&mut host), NetEvent::Moved(reply.clone()));
+    assert_eq!(next_event(&mut guest), NetEvent::Moved(reply));
+    assert_eq!(host.game().ply(), 2);
+    assert_eq!(guest.game().ply(), 2);
+}
+
+#[test]
+fn the_host_refuses_bad_moves() {
+    let mut host = host(Game::default());
+    let address = host.local_addr().unwrap();
+    let mut peer = Peer::new(TcpStream::connect(address).unwrap()).unwrap();
+    peer.send(&Message::Hello {
+        version: VERSION,
+        name: String::from("cheat"),
+    })
+    .unwrap();
+    next_event(&mut host);
+
+    let mut answers = vec![];
+    let board = Board::new();
+    let moves = board.legal_moves();
+    for text in [write_move(&moves[0]), String::from("1-2")] {
+        peer.send(&Message::Move(text)).unwrap();
+        host.poll().unwrap();
+    }
+    let deadline = Instant::now() + TIMEOUT;
+    while answers.len() < 3 && Instant::now() < deadline {
+        host.poll().unwrap();
+        if let Some(message) = peer.receive(Duration::from_millis(10)).unwrap() {
+            answers.push(message);
+        }
+    }
+
+    assert!(matches!(answers[0], Message::Welcome { .. }));
+    assert_eq!(
+        answers[1],
+        Message::Refused(String::from("It is not your turn"))
+    );
+    assert!(matches!(answers[2], Message::Refused(_)));
+    assert_eq!(host.game().ply(), 0);
+}
+
+#[test]
+fn only_one_guest_of_the_same_version_may_join() {
+    let mut host = host(Game::default());
+    let _guest = join(&mut host, "first").0.unwrap();
+    assert!(join(&mut host, "second").0.is_err());
+
+    let address = host.local_addr().unwrap();
+    let mut peer = Peer::new(TcpStream::connect(address).unwrap()).unwrap();
+    peer.send(&Message::Hello {
+        version: VERSION + 1,
+        name: String::from("future"),
+    })
+    .unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    let mut answer = None;
+    while answer.is_none() && Instant::now() < deadline {
+        host.poll().unwrap();
+        answer = peer.receive(Duration::from_millis(10)).unwrap();
+    }
+    assert!(matches!(answer, Some(Message::Refused(_))));
+}
+
+#[test]
+fn the_host_hears_when_the_guest_leaves() {
+    let mut host = host(Game::default()).with_reconnect_timeout(Duration::from_millis(50));
+    let address = host.local_addr().unwrap();
+    let watching = thread::spawn(move || Spectator::watch(address, "fan"));
+    while !watching.is_finished() {
+        host.poll().unwrap();
+    }
+    let mut spectator = watching.join().unwrap().unwrap();
+    let guest = join(&mut host, "guest").0.unwrap();
+
+    drop(guest);
+    assert_eq!(next_event(&mut host), NetEvent::Lost);
+    assert!(!host.is_connected());
+    // the seat is only kept for so long, then the game is lost by the absent side
+    assert_eq!(next_event(&mut host), NetEvent::Left);
+    assert_eq!(host.game().outcome(), Some(Outcome::Win(Side::Green)));
+    assert!(join(&mut host, "next").0.is_err());
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut seen = None;
+    while seen.is_none() && Instant::now() < deadline {
+        seen = spectator.poll().unwrap();
+    }
+    assert_eq!(seen, Some(NetEvent::Left));
+    assert_eq!(spectator.game().outcome(), Some(Outcome::Win(Side::Green)));
+}
+
+#[test]
+fn a_guest_resumes_its_session_with_the_game_as_it_stands() {
+    let mut host = host(Game::default());
+    let mut guest = join(&mut host, "guest").0.unwrap();
+    let session = guest.session();
+    let mv = first_move(host.game());
+    host.send_move(&mv).unwrap();
+    assert_eq!(next_event(&mut host), NetEvent::Moved(mv.clone()));
+    assert_eq!(next_event(&mut guest), NetEvent::Moved(mv));
+    thread::sleep(Duration::from_millis(20));
+
+    drop(guest);
+    assert_eq!(next_event(&mut host), NetEvent::Lost);
+    let used = host.clocks().used(Side::Red);
+    assert!(used >= Duration::from_millis(20));
+
+    // nobody else gets the seat meanwhile
+    assert!(join(&mut host, "intruder").0.is_err());
+    let address = host.local_addr().unwrap();
+    let resuming = thread::spawn(move || Guest::resume(address, "guest", session + 1));
+    while !resuming.is_finished() {
+        host.poll().unwrap();
+    }
+    assert!(resuming.join().unwrap().is_err());
+
+    let resuming = thread::spawn(move || Guest::resume(address, "guest", session));
+    let mut events = vec![];
+    while !resuming.is_finished() {
+        events.extend(host.poll().unwrap());
+    }
+    let guest = resuming.join().unwrap().unwrap();
+    assert_eq!(events, vec![NetEvent::Resumed]);
+    assert!(host.is_connected());
+    assert_eq!(guest.side(), Side::Red);
+    assert_eq!(guest.session(), session);
+    assert_eq!(
+        guest.game().moves().collect::<Vec<&Move>>(),
+        host.game().moves().collect::<Vec<&Move>>()
+    );
+    // the clocks stood still while the guest was away, they are told to the millisecond
+    assert_eq!(
+        guest.clocks().used(Side::Green).as_millis(),
+        host.clocks().used(Side::Green).as_millis()
+    );
+    assert!(guest.clocks().used(Side::Red).as_millis() >= used.as_millis());
+    assert!(guest.clocks().used(Side::Red) < used + Duration::from_secs(1));
+}
+
+#[test]
+fn a_guest_reconnects_by_itself_and_catches_up() {
+    let mut host = host(Game::default());
+    let proxy = Proxy::new(host.local_addr().unwrap());
+    let joining = thread::spawn(move || Guest::join(proxy.address, "guest").map(|g| (g, proxy)));
+    let mut events = vec![];
+    while !joining.is_finished() {
+        events.extend(host.poll().unwrap());
+    }
+    let (mut guest, proxy) = joining.join().unwrap().unwrap();
+    assert_eq!(events, vec![NetEvent::Joined(String::from("guest"))]);
+
+    proxy.cut();
+    assert_eq!(next_event(&mut guest), NetEvent::Lost);
+    assert!(!guest.is_connected());
+    assert_eq!(next_event(&mut host), NetEvent::Lost);
+
+    // a move the guest does not hear of until it is back
+    let mv = first_move(host.game());
+    host.send_move(&mv).unwrap();
+    assert_eq!(next_event(&mut host), NetEvent::Moved(mv.clone()));
+
+    let deadline = Instant::now() + TIMEOUT;
+    let (mut hosted, mut guested) = (vec![], vec![]);
+    while guested.len() < 2 && Instant::now() < deadline {
+        hosted.extend(host.poll().unwrap());
+        guested.extend(guest.poll().unwrap());
+    }
+    assert_eq!(hosted, vec![NetEvent::Resumed]);
+    assert_eq!(guested, vec![NetEvent::Moved(mv), NetEvent::Resumed]);
+    assert!(guest.is_connected());
+    assert_eq!(guest.game().ply(), 1);
+
+    let reply = first_move(guest.game());
+    guest.send_move(&reply).unwrap();
+    assert_eq!(next_event(&mut host), NetEvent::Moved(reply));
+}
+
+#[test]
+fn a_guest_gives_up_on_a_host_that_is_gone() {
+    let mut host = host(Game::default());
+    let mut guest = join(&mut host, "guest")
+        .0
+        .unwrap()
+        .with_reconnect_timeout(Duration::from_millis(50));
+
+    drop(host);
+    assert_eq!(next_event(&mut guest), NetEvent::Lost);
+    assert_eq!(next_event(&mut guest), NetEvent::Left);
+    assert!(!guest.is_connected());
+    // only the host could say who won
+    assert_eq!(guest.game().outcome(), None);
+}
+
+// a host that lets one player in and then plays a move that is no move at all
+fn cheating_host(watching: bool) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut peer = Peer::new(stream).unwrap();
+        peer.receive(TIMEOUT).unwrap();
+        peer.send(&Message::Welcome {
+            version: VERSION,
+            side: if watching { None } else { Some(Side::Red) },
+            session: if watching { None } else { Some(1) },
+            green_time: Duration::ZERO,
+            red_time: Duration::ZERO,
+            rules: Rules::default(),
+            start: Board::new(),
+            moves: vec![],
+        })
+        .unwrap();
+        peer.send(&Message::Move(String::from("9-99"))).unwrap();
+        // stays on the line until the other end hangs up
+        while peer.receive(TIMEOUT).is_ok() {}
+    });
+    address
+}
+
+#[test]
+fn a_guest_hangs_up_on_a_host_playing_illegal_moves() {
+    let mut guest = Guest::join(cheating_host(false), "guest").unwrap();
+    assert!(matches!(next_event(&mut guest), NetEvent::Failed(_)));
+    assert!(!guest.is_connected());
+    assert_eq!(guest.game().outcome(), Some(Outcome::Win(Side::Red)));
+}
+
+#[test]
+fn a_spectator_hangs_up_on_a_host_playing_illegal_moves() {
+    let mut spectator = Spectator::watch(cheating_host(true), "fan").unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    let mut event = None;
+    while event.is_none() && Instant::now() < deadline {
+        event = spectator.poll().unwrap();
+    }
+    assert!(matches!(event, Some(NetEvent::Failed(_))));
+    assert!(!spectator.is_connected());
+}
+
+#[test]
+fn the_host_shrugs_off_connections_talking_nonsense() {
+    let mut host = host(Game::default());
+    let address = host.local_addr().unwrap();
+    let mut junk = TcpStream::connect(address).unwrap();
+    io::Write::write_all(&mut junk, b"\x00\xff not a message\n").unwrap();
+    drop(junk);
+    for _ in 0..10 {
+        assert_eq!(host.poll().unwrap(), None);
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let (guest, events) = join(&mut host, "guest");
+    assert!(guest.is_ok());
+    assert_eq!(events, vec![NetEvent::Joined(String::from("guest"))]);
+}
+
+#[test]
+fn spectators_see_the_position_and_every_move() {
+    let mut game = Game::default();
+    game.play(&first_move(&game));
+    let mut host = host(game);
+
+    let address = host.local_addr().unwrap();
+    let watching = thread::spawn(move || Spectator::watch(address, "fan"));
+    while !watching.is_finished() {
+        host.poll().unwrap();
+        thread::sleep(Duration::from_millis(1));
+    }
+    let mut spectator = watching.join().unwrap().unwrap();
+    assert_eq!(spectator.game().ply(), 1);
+    assert_eq!(host.spectators(), 1);
+    // watching does not take the guest's place
+    assert!(!host.is_connected());
+
+    let mut guest = join(&mut host, "guest").0.unwrap();
+    let reply = first_move(guest.game());
+    guest.send_move(&reply).unwrap();
+    assert_eq!(next_event(&mut host), NetEvent::Moved(reply.clone()));
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut seen = None;
+    while seen.is_none() && Instant::now() < deadline {
+        seen = spectator.poll().unwrap();
+    }
+    assert_eq!(seen, Some(NetEvent::Moved(reply)));
+    assert_eq!(spectator.game().ply(), 2);
+
+    drop(host);
+    let mut left = None;
+    let deadline = Instant::now() + TIMEOUT;
+    while left.is_none() && Instant::now() < deadline {
+        left = spectator.poll().unwrap();
+    }
+    assert_eq!(left, Some(NetEvent::Left));
+}
+
+#[test]
+fn chat_reaches_everyone_under_the_senders_name() {
+    let mut host = Host::bind("127.0.0.1:0", Side::Green, Game::default())
+        .unwrap()
+        .with_name("host");
+    let address = host.local_addr().unwrap();
+    let watching = thread::spawn(move || Spectator::watch(address, "fan"));
+    while !watching.is_finished() {
+        host.poll().unwrap();
+        thread::sleep(Duration::from_millis(1));
+    }
+    let mut spectator = watching.join().unwrap().unwrap();
+    let mut guest = join(&mut host, "guest").0.unwrap();
+
+    let said = |name: &str, text: &str| NetEvent::Chat {
+        name: String::from(name),
+        text: String::from(text),
+    };
+    host.send_chat("hello").unwrap();
+    assert_eq!(next_event(&mut host), said("host", "hello"));
+    assert_eq!(next_event(&mut guest), said("host", "hello"));
+
+    guest.send_chat("hi there").unwrap();
+    assert_eq!(next_event(&mut host), said("guest", "hi there"));
+    assert_eq!(next_event(&mut guest), said("guest", "hi there"));
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut heard = vec![];
+    while heard.len() < 2 && Instant::now() < deadline {
+        heard.extend(spectator.poll().unwrap());
+    }
+    assert_eq!(
+        heard,
+        vec![said("host", "hello"), said("guest", "hi there")]
+    );
+}
+
+#[test]
+fn a_guest_cannot_chat_under_another_name() {
+    let mut host = host(Game::default());
+    let address = host.local_addr().unwrap();
+    let mut peer = Peer::new(TcpStream::connect(address).unwrap()).unwrap();
+    peer.send(&Message::Hello {
+        version: VERSION,
+        name: String::from("guest"),
+    })
+    .unwrap();
+    next_event(&mut host);
+
+    peer.send(&Message::Chat {
+        name: String::from("Host"),
+        text: String::from("I resign"),
+    })
+    .unwrap();
+    assert_eq!(
+        next_event(&mut host),
+        NetEvent::Chat {
+            name: String::from("guest"),
+            text: String::from("I resign"),
+        }
+    );
+}
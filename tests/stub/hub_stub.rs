@@ -0,0 +1,63 @@
+//! A stand-in Hub engine for the tests: it always plays the first legal move, and when given a
+//! move time it thinks until it is told to stop.
+
+use checkers::hub::{parse_move, parse_position, write_move, Message};
+use checkers::rules::{variant_rules, Board, Rules};
+use std::io::{self, BufRead, Write};
+
+fn main() -> Result<(), String> {
+    let stdin = io::stdin();
+    let mut rules = Rules::default();
+    let mut board = Board::new();
+    let mut move_time = None;
+
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let message = match Message::parse(&line) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+
+        match message.command.as_ref() {
+            "hub" => {
+                reply("id name=stub version=1.0");
+                reply("param name=variant value=normal type=enum values=\"normal majority\"");
+                reply("wait");
+            }
+            "set-param" if message.get("name") == Some("variant") => {
+                rules = message
+                    .get("value")
+                    .and_then(variant_rules)
+                    .ok_or("Unknown variant")?;
+            }
+            "init" => reply("ready"),
+            "ping" => reply("pong"),
+            "pos" => {
+                board = parse_position(message.get("pos").unwrap_or(""))
+                    .map(|b| b.with_rules(rules))?;
+                for text in message.get("moves").unwrap_or("").split_whitespace() {
+                    board.apply(&parse_move(&board, text)?);
+                }
+            }
+            "level" => move_time = message.get("move-time").map(String::from),
+            "go" if move_time.is_some() => {}
+            "go" | "stop" => {
+                let best = board
+                    .legal_moves()
+                    .into_iter()
+                    .next()
+                    .ok_or("No legal move")?;
+                reply("info depth=1 score=0.25 nodes=1");
+                reply(&format!("done move={}", write_move(&best)));
+            }
+            "quit" => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn reply(text: &str) {
+    println!("{}", text);
+    io::stdout().flush().unwrap();
+}